@@ -8,14 +8,13 @@ use egui_snarl::{
     InPin, InPinId, NodeId, OutPin, OutPinId, Snarl,
 };
 use serde::{Deserialize, Serialize};
-use interactivity::{ExistingValues, get_registry, MathAdd, MathPi, NodeArchetype, NodeArchetypeIncomplete, NodeArchetypes, NodeBehavior, NodeBehaviors, OutputFlowSocket, OutputValueSocket, PrintNode, RegisterNode, SequenceNode};
+use interactivity::{Configuration, Declarations, Graph, Node, NodeBehavior, NodeBehaviors, NodeRegistry, Runtime};
 
 const STRING_COLOR: Color32 = Color32::from_rgb(0x00, 0xb0, 0x00);
 const NUMBER_COLOR: Color32 = Color32::from_rgb(0xb0, 0x00, 0x00);
 const IMAGE_COLOR: Color32 = Color32::from_rgb(0xb0, 0x00, 0xb0);
 const UNTYPED_COLOR: Color32 = Color32::from_rgb(0xb0, 0xb0, 0xb0);
 pub fn main() {
-    register();
     let native_options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
             .with_inner_size([400.0, 300.0])
@@ -30,17 +29,11 @@ pub fn main() {
     ).unwrap();
 }
 
-pub fn register() {
-    MathAdd::register();
-    MathPi::register();
-    SequenceNode::register();
-    PrintNode::register();
-}
-
 pub struct DemoApp {
-    snarl: Snarl<NodeArchetypeIncomplete>,
+    snarl: Snarl<Node>,
     style: SnarlStyle,
     interactivity_viewer: InteractivityViewer,
+    runtime: Option<Runtime>,
 }
 
 impl DemoApp {
@@ -70,17 +63,17 @@ impl DemoApp {
         };
 
         let interactivity_viewer = match cx.storage {
-            None => InteractivityViewer { number_nodes: 0 },
+            None => InteractivityViewer { number_nodes: 0, registry: NodeRegistry::builtin() },
             Some(storage) => {
                 let interactivity_viewer = storage
                     .get_string("interactivity_viewer")
                     .and_then(|interactivity_viewer| serde_json::from_str(&interactivity_viewer).ok())
-                    .unwrap_or_else(|| InteractivityViewer { number_nodes: 0 });
+                    .unwrap_or_else(|| InteractivityViewer { number_nodes: 0, registry: NodeRegistry::builtin() });
                 interactivity_viewer
             }
         };
 
-        DemoApp { snarl, style, interactivity_viewer }
+        DemoApp { snarl, style, interactivity_viewer, runtime: None }
     }
 }
 
@@ -109,31 +102,28 @@ impl App for DemoApp {
                 }
 
                 if ui.button("run").clicked() {
-                    let mut node_archetypes = NodeArchetypes(HashMap::default());
-                    let mut behaviors = NodeBehaviors(HashMap::default());
-                    let mut sequence = 0;
-                    for node_archetype in self.snarl.nodes() {
-                        let registry = get_registry();
-                        let registry = registry.lock().unwrap();
-                        let (_, node_func) = registry.get(&node_archetype.name).unwrap();
-                        let node = node_func(node_archetype.node_id);
-                        behaviors.0.insert(node_archetype.node_id, node);
-                        if node_archetype.name.as_str() == "flow/sequence" {
-                            sequence = node_archetype.node_id;
+                    match Runtime::load(&self.interactivity_viewer.registry, &graph(&self.snarl)) {
+                        Ok(mut runtime) => {
+                            runtime.start();
+                            self.runtime = Some(runtime);
                         }
-                        node_archetypes.0.insert(
-                            node_archetype.node_id,
-                            node_archetype.clone().into()
-                        );
+                        Err(error) => eprintln!("{error}"),
                     }
-                    let mut existing_values = ExistingValues::default();
-                    existing_values.flow(sequence, &node_archetypes, &mut behaviors);
+                }
+
+                if self.runtime.is_some() && ui.button("stop").clicked() {
+                    self.runtime = None;
                 }
 
             });
 
         });
 
+        if let Some(runtime) = self.runtime.as_mut() {
+            runtime.tick(ctx.input(|i| i.stable_dt));
+            ctx.request_repaint();
+        }
+
         egui::SidePanel::left("style").show(ctx, |ui| {
             egui::ScrollArea::vertical().show(ui, |ui| {
                 egui_probe::Probe::new("Snarl style", &mut self.style).show(ui);
@@ -161,23 +151,25 @@ impl App for DemoApp {
 
 #[derive(Serialize, Deserialize)]
 struct InteractivityViewer {
-    number_nodes: usize
+    number_nodes: usize,
+    #[serde(skip, default = "NodeRegistry::builtin")]
+    registry: NodeRegistry,
 }
 
-impl SnarlViewer<NodeArchetypeIncomplete> for InteractivityViewer {
-    fn title(&mut self, node: &NodeArchetypeIncomplete) -> String {
-        node.name.clone()
+impl SnarlViewer<Node> for InteractivityViewer {
+    fn title(&mut self, node: &Node) -> String {
+        node.op.clone()
     }
 
-    fn outputs(&mut self, node: &NodeArchetypeIncomplete) -> usize {
+    fn outputs(&mut self, node: &Node) -> usize {
         node.output_value_sockets.len() + node.output_flow_sockets.len()
     }
 
-    fn inputs(&mut self, node: &NodeArchetypeIncomplete) -> usize {
+    fn inputs(&mut self, node: &Node) -> usize {
         node.input_value_sockets.len() + node.input_flow_sockets.len()
     }
 
-    fn show_input(&mut self, pin: &InPin, ui: &mut Ui, scale: f32, snarl: &mut Snarl<NodeArchetypeIncomplete>) -> PinInfo {
+    fn show_input(&mut self, pin: &InPin, ui: &mut Ui, scale: f32, snarl: &mut Snarl<Node>) -> PinInfo {
         let node = &snarl[pin.id.node];
         let color_type = if pin.remotes.len() == 0 { UNTYPED_COLOR } else { NUMBER_COLOR };
         if node.input_value_sockets.len() <= pin.id.input {
@@ -187,7 +179,7 @@ impl SnarlViewer<NodeArchetypeIncomplete> for InteractivityViewer {
         }
     }
 
-    fn show_output(&mut self, pin: &OutPin, ui: &mut Ui, scale: f32, snarl: &mut Snarl<NodeArchetypeIncomplete>) -> PinInfo {
+    fn show_output(&mut self, pin: &OutPin, ui: &mut Ui, scale: f32, snarl: &mut Snarl<Node>) -> PinInfo {
         let node = &snarl[pin.id.node];
         let color_type = if pin.remotes.len() == 0 { UNTYPED_COLOR } else { NUMBER_COLOR };
         if node.output_value_sockets.len() <= pin.id.output {
@@ -197,48 +189,62 @@ impl SnarlViewer<NodeArchetypeIncomplete> for InteractivityViewer {
         }
     }
 
-    fn input_color(&mut self, pin: &InPin, style: &Style, snarl: &mut Snarl<NodeArchetypeIncomplete>) -> Color32 {
+    fn input_color(&mut self, pin: &InPin, style: &Style, snarl: &mut Snarl<Node>) -> Color32 {
         let node = &snarl[pin.id.node];
         let color_type = if pin.remotes.len() == 0 { UNTYPED_COLOR } else { NUMBER_COLOR };
         color_type
     }
 
-    fn output_color(&mut self, pin: &OutPin, style: &Style, snarl: &mut Snarl<NodeArchetypeIncomplete>) -> Color32 {
+    fn output_color(&mut self, pin: &OutPin, style: &Style, snarl: &mut Snarl<Node>) -> Color32 {
         let node = &snarl[pin.id.node];
         let color_type = if pin.remotes.len() == 0 { UNTYPED_COLOR } else { NUMBER_COLOR };
         color_type
     }
 
-    fn graph_menu(&mut self, pos: Pos2, ui: &mut Ui, scale: f32, snarl: &mut Snarl<NodeArchetypeIncomplete>) {
+    fn graph_menu(&mut self, pos: Pos2, ui: &mut Ui, scale: f32, snarl: &mut Snarl<Node>) {
         ui.label("Add node");
-        let registry = get_registry();
-        for (key, (value, _)) in registry.lock().unwrap().iter() {
-            if ui.button(key).clicked() {
-                snarl.insert_node(pos, value(self.number_nodes as u32));
-                self.number_nodes += 1;
-            }
+        let descriptors = self.registry.descriptors();
+        let mut categories = descriptors.iter().map(|a| a.category.as_str()).collect::<Vec<_>>();
+        categories.dedup();
+        for category in categories {
+            ui.menu_button(category, |ui| {
+                for descriptor in descriptors.iter().filter(|a| a.category == category) {
+                    if ui.button(&descriptor.op).on_hover_text(&descriptor.description).clicked() {
+                        match self.registry.build(&descriptor.op, self.number_nodes as u32, &Configuration::new(), &Declarations::new()) {
+                            Ok(node) => {
+                                snarl.insert_node(pos, node);
+                                self.number_nodes += 1;
+                            }
+                            Err(error) => eprintln!("{error}"),
+                        }
+                        ui.close_menu();
+                    }
+                }
+            });
         }
     }
 
-    fn connect(&mut self, from: &OutPin, to: &InPin, snarl: &mut Snarl<NodeArchetypeIncomplete>) {
+    fn connect(&mut self, from: &OutPin, to: &InPin, snarl: &mut Snarl<Node>) {
         snarl.connect(from.id, to.id);
-        let output_value_socket = snarl.get_node(from.id.node).unwrap();
+    }
+}
 
-        if from.id.output < output_value_socket.output_value_sockets.len() {
-            let output_value_socket = output_value_socket.output_value_sockets.get(from.id.output).unwrap().clone();
-            let input_value_socket = snarl.get_node_mut(to.id.node).unwrap();
-            let input_value_socket = input_value_socket.input_value_sockets.get_mut(to.id.input).unwrap();
-            input_value_socket.output_value_socket.replace(output_value_socket.clone());
-        } else {
-            let output_flow_socket = output_value_socket.output_flow_sockets.get(from.id.output - output_value_socket.output_value_sockets.len()).unwrap().clone();
-            let input_flow_socket = snarl.get_node_mut(to.id.node).unwrap();
-            let input_flow_socket = input_flow_socket.input_flow_sockets.get_mut(to.id.input - input_flow_socket
-                .input_value_sockets.len()).unwrap();
-            input_flow_socket.output_flow_socket.replace(output_flow_socket);
-            let input_flow_socket = input_flow_socket.clone();
-            let output_value_socket = snarl.get_node_mut(from.id.node).unwrap();
-            let output_flow_socket = output_value_socket.output_flow_sockets.get_mut(from.id.output - output_value_socket.output_value_sockets.len()).unwrap();
-            output_flow_socket.input_flow_socket.replace(Box::new(input_flow_socket));
+/// The graph drawn in the editor: its nodes, and a link for every wire. Value
+/// pins come before flow pins on both sides of a node.
+fn graph(snarl: &Snarl<Node>) -> Graph {
+    let mut graph = Graph::new();
+    for node in snarl.nodes() {
+        graph.add_node(node.clone());
+    }
+    for (from, to) in snarl.wires() {
+        let (output, input) = (&snarl[from.node], &snarl[to.node]);
+        if from.output < output.output_value_sockets.len() {
+            if let Some(socket) = input.input_value_sockets.get(to.input) {
+                graph.link_value(output.node_id, &output.output_value_sockets[from.output], input.node_id, socket);
+            }
+        } else if let Some(socket) = to.input.checked_sub(input.input_value_sockets.len()).and_then(|a| input.input_flow_sockets.get(a)) {
+            graph.link_flow(output.node_id, &output.output_flow_sockets[from.output - output.output_value_sockets.len()], input.node_id, socket);
         }
     }
-}
\ No newline at end of file
+    graph
+}
@@ -0,0 +1,265 @@
+use serde::{Deserialize, Serialize};
+use crate::{node, Completion, ExecutionContext, NodeId};
+
+/// `animation/start`, plays an animation of the world and activates `out`, or
+/// `err` if the inputs are invalid or the world has no such animation. `done`
+/// activates later, once the host reports the animation finished.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct AnimationStartNode {
+    node_id: NodeId,
+}
+
+impl AnimationStartNode {
+    pub fn new(node_id: NodeId) -> Self {
+        Self {
+            node_id,
+        }
+    }
+
+    fn start(&self, ctx: &mut ExecutionContext) -> Option<i32> {
+        let animation = self.animation(ctx)?;
+        let start_time = self.start_time(ctx)?;
+        let end_time = self.end_time(ctx)?;
+        let speed = self.speed(ctx)?;
+        if !start_time.is_finite() || end_time.is_nan() || !speed.is_finite() || speed <= 0.0 {
+            return None;
+        }
+        ctx.world.start_animation(animation, start_time, end_time, speed)?;
+        Some(animation)
+    }
+}
+
+node! {
+    AnimationStartNode {
+        op: "animation/start",
+        kind: Flow,
+        description: "Plays an animation, activating `done` once it finishes.",
+        input_values: {
+            animation: i32,
+            start_time = "startTime": f32,
+            end_time = "endTime": f32,
+            speed: f32,
+        },
+        input_flows: ["in"],
+        output_flows: ["out", "err", "done"],
+        new_node: |node_id| Self::new(node_id),
+    }
+    impl NodeBehavior {
+        fn value(&mut self, _: &mut ExecutionContext) -> Option<()> {
+            panic!("cannot request value from this node");
+        }
+
+        /// Restarting an animation replaces whatever `done` flows were waiting for it.
+        fn activate_input_node(&mut self, ctx: &mut ExecutionContext) -> Option<()> {
+            match self.start(ctx) {
+                Some(animation) => {
+                    let completion = Completion::Animation(animation);
+                    ctx.cancel(&completion);
+                    ctx.defer(self.node_id, "done", completion);
+                    ctx.fire(self.node_id, "out")
+                }
+                None => ctx.fire(self.node_id, "err"),
+            }
+        }
+    }
+}
+
+/// `animation/stop`, stops an animation of the world right away and activates
+/// `out`, or `err` if the world has no such animation. The `done` flows
+/// waiting for the animation never activate.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct AnimationStopNode {
+    node_id: NodeId,
+}
+
+impl AnimationStopNode {
+    pub fn new(node_id: NodeId) -> Self {
+        Self {
+            node_id,
+        }
+    }
+
+    fn stop(&self, ctx: &mut ExecutionContext) -> Option<i32> {
+        let animation = self.animation(ctx)?;
+        ctx.world.stop_animation(animation)?;
+        Some(animation)
+    }
+}
+
+node! {
+    AnimationStopNode {
+        op: "animation/stop",
+        kind: Flow,
+        description: "Stops an animation right away.",
+        input_values: { animation: i32 },
+        input_flows: ["in"],
+        output_flows: ["out", "err"],
+        new_node: |node_id| Self::new(node_id),
+    }
+    impl NodeBehavior {
+        fn value(&mut self, _: &mut ExecutionContext) -> Option<()> {
+            panic!("cannot request value from this node");
+        }
+
+        fn activate_input_node(&mut self, ctx: &mut ExecutionContext) -> Option<()> {
+            match self.stop(ctx) {
+                Some(animation) => {
+                    ctx.cancel(&Completion::Animation(animation));
+                    ctx.fire(self.node_id, "out")
+                }
+                None => ctx.fire(self.node_id, "err"),
+            }
+        }
+    }
+}
+
+/// `animation/stopAt`, tells an animation of the world to stop once it reaches
+/// `stopTime` and activates `out`, or `err` if the inputs are invalid or the
+/// world has no such animation. `done` activates later, once the host reports
+/// the animation stopped, in place of the `done` of `animation/start`.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct AnimationStopAtNode {
+    node_id: NodeId,
+}
+
+impl AnimationStopAtNode {
+    pub fn new(node_id: NodeId) -> Self {
+        Self {
+            node_id,
+        }
+    }
+
+    fn stop_at(&self, ctx: &mut ExecutionContext) -> Option<i32> {
+        let animation = self.animation(ctx)?;
+        let stop_time = self.stop_time(ctx)?;
+        if stop_time.is_nan() {
+            return None;
+        }
+        ctx.world.stop_animation_at(animation, stop_time)?;
+        Some(animation)
+    }
+}
+
+node! {
+    AnimationStopAtNode {
+        op: "animation/stopAt",
+        kind: Flow,
+        description: "Stops an animation once it reaches `stopTime`, activating `done` then.",
+        input_values: { animation: i32, stop_time = "stopTime": f32 },
+        input_flows: ["in"],
+        output_flows: ["out", "err", "done"],
+        new_node: |node_id| Self::new(node_id),
+    }
+    impl NodeBehavior {
+        fn value(&mut self, _: &mut ExecutionContext) -> Option<()> {
+            panic!("cannot request value from this node");
+        }
+
+        fn activate_input_node(&mut self, ctx: &mut ExecutionContext) -> Option<()> {
+            match self.stop_at(ctx) {
+                Some(animation) => {
+                    let completion = Completion::Animation(animation);
+                    ctx.cancel(&completion);
+                    ctx.defer(self.node_id, "done", completion);
+                    ctx.fire(self.node_id, "out")
+                }
+                None => ctx.fire(self.node_id, "err"),
+            }
+        }
+    }
+}
+
+#[test]
+fn test_animation_start_and_done() {
+    use std::cell::Cell;
+    use std::collections::HashMap;
+    use std::rc::Rc;
+    use crate::{EventDeclaration, EventSendNode, Graph, NodeBehavior, NodeBehaviors, OnStartNode, Runtime, Value, ValueType, VariableDeclaration, VariableGetNode, World};
+
+    /// Plays animation 0 only.
+    #[derive(Default)]
+    struct TestWorld {
+        playing: Vec<i32>,
+    }
+
+    impl World for TestWorld {
+        fn read_pointer(&self, _: &str) -> Option<Value> {
+            None
+        }
+
+        fn write_pointer(&mut self, _: &str, _: Value) -> Option<()> {
+            None
+        }
+
+        fn start_animation(&mut self, animation: i32, _: f32, _: f32, _: f32) -> Option<()> {
+            (animation == 0).then(|| self.playing.push(animation))
+        }
+    }
+
+    let mut graph = Graph::new();
+    let mut node_behaviors = NodeBehaviors(HashMap::new());
+
+    let start_id = 0;
+    let animation_start_id = 1;
+    let send_id = 2;
+    let animation_id = 3;
+    let time_id = 4;
+
+    let finished = EventDeclaration {
+        id: "finished".to_string(),
+        values: vec![],
+    };
+    let start = OnStartNode::new(start_id);
+    let animation_start = AnimationStartNode::new(animation_start_id);
+    let send = EventSendNode::new(send_id, 0, &finished);
+    let animation = VariableGetNode::new(animation_id, 0);
+    let time = VariableGetNode::new(time_id, 1);
+
+    graph
+        .add_node(start.node())
+        .add_node(animation_start.node())
+        .add_node(send.node())
+        .add_node(animation.node())
+        .add_node(time.node())
+        .link_flow(start_id, "out", animation_start_id, "in")
+        .link_value(animation_id, "value", animation_start_id, "animation")
+        .link_value(time_id, "value", animation_start_id, "startTime")
+        .link_value(time_id, "value", animation_start_id, "endTime")
+        .link_value(time_id, "value", animation_start_id, "speed")
+        .link_flow(animation_start_id, "done", send_id, "in");
+
+    node_behaviors.add(Box::new(start));
+    node_behaviors.add(Box::new(animation_start));
+    node_behaviors.add(Box::new(send));
+    node_behaviors.add(Box::new(animation));
+    node_behaviors.add(Box::new(time));
+
+    let mut runtime = Runtime::with_world(graph.resolve().unwrap(), node_behaviors, TestWorld::default());
+    runtime.add_variable(VariableDeclaration {
+        name: "animation".to_string(),
+        value_type: ValueType::Int,
+        value: Value::Int(0),
+    }).unwrap();
+    runtime.add_variable(VariableDeclaration {
+        name: "time".to_string(),
+        value_type: ValueType::Float,
+        value: Value::Float(1.0),
+    }).unwrap();
+    let event = runtime.add_event(finished);
+    let done = Rc::new(Cell::new(0));
+    runtime.subscribe(event, {
+        let done = done.clone();
+        move |_| done.set(done.get() + 1)
+    });
+
+    runtime.start();
+    assert_eq!(runtime.world().playing, vec![0]);
+    assert_eq!(runtime.pending_flows().len(), 1);
+    assert_eq!(done.get(), 0);
+
+    runtime.complete_animation(1);
+    assert_eq!(done.get(), 0);
+    runtime.complete_animation(0);
+    assert_eq!(done.get(), 1);
+    assert!(runtime.pending_flows().is_empty());
+}
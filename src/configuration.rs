@@ -0,0 +1,244 @@
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use serde::{Deserialize, Serialize};
+use crate::{ConfigurationType, NodeDescriptor};
+
+/// A configuration entry of a node, such as the `variable` of `variable/get`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ConfigurationValue {
+    Bool(bool),
+    Int(i32),
+    IntArray(Vec<i32>),
+    String(String),
+    StringArray(Vec<String>),
+}
+
+impl ConfigurationValue {
+    pub fn configuration_type(&self) -> ConfigurationType {
+        match self {
+            ConfigurationValue::Bool(_) => ConfigurationType::Bool,
+            ConfigurationValue::Int(_) => ConfigurationType::Int,
+            ConfigurationValue::IntArray(_) => ConfigurationType::IntArray,
+            ConfigurationValue::String(_) => ConfigurationType::String,
+            ConfigurationValue::StringArray(_) => ConfigurationType::StringArray,
+        }
+    }
+
+    /// Whether the entry holds a value of `configuration_type`. An empty array
+    /// is read back from JSON as an [`ConfigurationValue::IntArray`], so it
+    /// also counts as a string array.
+    pub fn is(&self, configuration_type: ConfigurationType) -> bool {
+        match self {
+            ConfigurationValue::IntArray(a) if a.is_empty() => matches!(configuration_type, ConfigurationType::IntArray | ConfigurationType::StringArray),
+            _ => self.configuration_type() == configuration_type,
+        }
+    }
+}
+
+impl From<bool> for ConfigurationValue {
+    fn from(value: bool) -> Self {
+        ConfigurationValue::Bool(value)
+    }
+}
+
+impl From<i32> for ConfigurationValue {
+    fn from(value: i32) -> Self {
+        ConfigurationValue::Int(value)
+    }
+}
+
+impl From<Vec<i32>> for ConfigurationValue {
+    fn from(value: Vec<i32>) -> Self {
+        ConfigurationValue::IntArray(value)
+    }
+}
+
+impl From<String> for ConfigurationValue {
+    fn from(value: String) -> Self {
+        ConfigurationValue::String(value)
+    }
+}
+
+impl From<Vec<String>> for ConfigurationValue {
+    fn from(value: Vec<String>) -> Self {
+        ConfigurationValue::StringArray(value)
+    }
+}
+
+impl From<&str> for ConfigurationValue {
+    fn from(value: &str) -> Self {
+        ConfigurationValue::String(value.to_string())
+    }
+}
+
+#[derive(Debug, PartialEq, thiserror::Error)]
+pub enum ConfigurationError {
+    #[error("configuration `{0}` is not declared by the node type")]
+    Unknown(String),
+    #[error("configuration `{name}` takes {expected:?}, not {found:?}")]
+    TypeMismatch { name: String, expected: ConfigurationType, found: ConfigurationType },
+    #[error("configuration `{name}` is invalid: {reason}")]
+    Invalid { name: String, reason: String },
+}
+
+impl ConfigurationError {
+    pub fn invalid(name: impl ToString, reason: impl Display) -> Self {
+        ConfigurationError::Invalid {
+            name: name.to_string(),
+            reason: reason.to_string(),
+        }
+    }
+}
+
+/// The configuration of a node by entry name. Entries left out take the
+/// default of the node type, so an empty configuration gives the node a
+/// palette creates.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Configuration(BTreeMap<String, ConfigurationValue>);
+
+impl Configuration {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with(mut self, name: impl ToString, value: impl Into<ConfigurationValue>) -> Self {
+        self.set(name, value);
+        self
+    }
+
+    pub fn set(&mut self, name: impl ToString, value: impl Into<ConfigurationValue>) {
+        self.0.insert(name.to_string(), value.into());
+    }
+
+    pub fn get(&self, name: &str) -> Option<&ConfigurationValue> {
+        self.0.get(name)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &ConfigurationValue)> {
+        self.0.iter().map(|(a, b)| (a.as_str(), b))
+    }
+
+    /// Checks that every entry is declared by `descriptor`, with its type.
+    pub fn check(&self, descriptor: &NodeDescriptor) -> Result<(), ConfigurationError> {
+        for (name, value) in &self.0 {
+            let declared = descriptor.configuration.iter().find(|a| &a.name == name)
+                .ok_or_else(|| ConfigurationError::Unknown(name.clone()))?;
+            if !value.is(declared.configuration_type) {
+                return Err(ConfigurationError::TypeMismatch {
+                    name: name.clone(),
+                    expected: declared.configuration_type,
+                    found: value.configuration_type(),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn typed<'a, T>(&'a self, name: &str, expected: ConfigurationType, f: impl FnOnce(&'a ConfigurationValue) -> Option<T>) -> Result<Option<T>, ConfigurationError> {
+        let Some(value) = self.0.get(name) else {
+            return Ok(None);
+        };
+        f(value).map(Some).ok_or_else(|| ConfigurationError::TypeMismatch {
+            name: name.to_string(),
+            expected,
+            found: value.configuration_type(),
+        })
+    }
+
+    pub fn bool(&self, name: &str) -> Result<Option<bool>, ConfigurationError> {
+        self.typed(name, ConfigurationType::Bool, |a| match a {
+            ConfigurationValue::Bool(value) => Some(*value),
+            _ => None,
+        })
+    }
+
+    pub fn int(&self, name: &str) -> Result<Option<i32>, ConfigurationError> {
+        self.typed(name, ConfigurationType::Int, |a| match a {
+            ConfigurationValue::Int(value) => Some(*value),
+            _ => None,
+        })
+    }
+
+    pub fn int_array(&self, name: &str) -> Result<Option<&[i32]>, ConfigurationError> {
+        self.typed(name, ConfigurationType::IntArray, |a| match a {
+            ConfigurationValue::IntArray(value) => Some(value.as_slice()),
+            _ => None,
+        })
+    }
+
+    pub fn string(&self, name: &str) -> Result<Option<&str>, ConfigurationError> {
+        self.typed(name, ConfigurationType::String, |a| match a {
+            ConfigurationValue::String(value) => Some(value.as_str()),
+            _ => None,
+        })
+    }
+
+    pub fn string_array(&self, name: &str) -> Result<Option<Vec<String>>, ConfigurationError> {
+        self.typed(name, ConfigurationType::StringArray, |a| match a {
+            ConfigurationValue::StringArray(value) => Some(value.clone()),
+            ConfigurationValue::IntArray(value) if value.is_empty() => Some(vec![]),
+            _ => None,
+        })
+    }
+
+    /// An int entry used as an index, such as that of a variable or event.
+    pub fn index(&self, name: &str) -> Result<Option<usize>, ConfigurationError> {
+        self.int(name)?
+            .map(|a| usize::try_from(a).map_err(|_| ConfigurationError::invalid(name, "an index cannot be negative")))
+            .transpose()
+    }
+
+    /// An int array entry used as indices.
+    pub fn indices(&self, name: &str) -> Result<Option<Vec<usize>>, ConfigurationError> {
+        self.int_array(name)?
+            .map(|a| a.iter().map(|b| usize::try_from(*b)).collect::<Result<Vec<_>, _>>())
+            .transpose()
+            .map_err(|_| ConfigurationError::invalid(name, "an index cannot be negative"))
+    }
+}
+
+#[test]
+fn test_configuration() {
+    use crate::{Declarations, LogNode, NodeArchetypeBuilder, NodeBehavior, Severity, Value, ValueType, VariableDeclaration, VariableSetMultipleNode};
+
+    let mut declarations = Declarations::new();
+    for name in ["a", "b", "c"] {
+        declarations.variables.push(VariableDeclaration {
+            name: name.to_string(),
+            value_type: ValueType::Float,
+            value: Value::Float(0.0),
+        });
+    }
+    let log = LogNode::new(0, "{a} and {b}", Severity::Warning);
+    let node = log.node();
+    assert_eq!(node.configuration, Configuration::new().with("message", "{a} and {b}").with("severity", 1));
+    assert_eq!(node.input_value_sockets, vec!["a", "b"]);
+    // The configuration alone gives back the node, sockets included.
+    assert_eq!(LogNode::build(0, &node.configuration, &declarations).unwrap(), node);
+    assert_eq!(LogNode::build(0, &Configuration::new(), &declarations).unwrap().input_value_sockets, Vec::<String>::new());
+
+    let json = serde_json::to_string(&node.configuration).unwrap();
+    assert_eq!(json, r#"{"message":"{a} and {b}","severity":1}"#);
+    assert_eq!(serde_json::from_str::<Configuration>(&json).unwrap(), node.configuration);
+
+    assert_eq!(
+        LogNode::new_node(0, &Configuration::new().with("severity", "high"), &declarations).err(),
+        Some(ConfigurationError::TypeMismatch { name: "severity".to_string(), expected: ConfigurationType::Int, found: ConfigurationType::String })
+    );
+    assert_eq!(
+        LogNode::new_node(0, &Configuration::new().with("isRandom", true), &declarations).err(),
+        Some(ConfigurationError::Unknown("isRandom".to_string()))
+    );
+    assert!(matches!(
+        VariableSetMultipleNode::new_node(0, &Configuration::new().with("variables", vec![0, -1]), &declarations),
+        Err(ConfigurationError::Invalid { .. })
+    ));
+    let set_multiple = VariableSetMultipleNode::build(0, &Configuration::new().with("variables", vec![2, 0]), &declarations).unwrap();
+    assert_eq!(set_multiple.input_value_sockets, vec!["2", "0"]);
+}
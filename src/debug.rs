@@ -0,0 +1,152 @@
+use serde::{Deserialize, Serialize};
+use crate::{Activation, NodeId};
+
+/// Where [`crate::Runtime`] pauses execution, see [`crate::Runtime::add_breakpoint`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Breakpoint {
+    /// Before the node is activated.
+    Node(NodeId),
+    /// Before whatever the output flow socket `socket` of `node_id` is linked
+    /// to is activated.
+    Flow {
+        node_id: NodeId,
+        socket: String,
+    },
+}
+
+impl Breakpoint {
+    pub fn matches(&self, activation: &Activation) -> bool {
+        match self {
+            Breakpoint::Node(node_id) => activation.node_id == *node_id,
+            Breakpoint::Flow { node_id, socket } => activation.fired_by.as_ref().is_some_and(|(a, b)| a == node_id && b == socket),
+        }
+    }
+}
+
+#[test]
+fn test_debugger() {
+    use std::collections::HashMap;
+    use crate::{CollectingLogSink, Graph, LogNode, NodeBehavior, NodeBehaviors, OnTickNode, Runtime, Severity, Value, ValueType, VariableDeclaration};
+
+    let mut graph = Graph::new();
+    let mut node_behaviors = NodeBehaviors(HashMap::new());
+
+    let tick_id = 0;
+    let first_id = 1;
+    let second_id = 2;
+
+    let tick = OnTickNode::new(tick_id);
+    let first = LogNode::new(first_id, "first", Severity::Info);
+    let second = LogNode::new(second_id, "second", Severity::Info);
+
+    graph
+        .add_node(tick.node())
+        .add_node(first.node())
+        .add_node(second.node())
+        .link_flow(tick_id, "out", first_id, "in")
+        .link_flow(first_id, "out", second_id, "in");
+
+    node_behaviors.add(Box::new(tick));
+    node_behaviors.add(Box::new(first));
+    node_behaviors.add(Box::new(second));
+
+    let sink = CollectingLogSink::default();
+    let mut runtime = Runtime::new(graph.resolve().unwrap(), node_behaviors);
+    runtime.set_log_sink(sink.clone());
+
+    runtime.add_breakpoint(Breakpoint::Node(first_id));
+    runtime.tick(0.5);
+    assert_eq!(runtime.paused(), Some(&Activation {
+        node_id: first_id,
+        fired_by: Some((tick_id, "out".to_string())),
+    }));
+    assert_eq!(runtime.existing_values().get_value(tick_id, "timeSinceStart"), Some(&Value::Float(0.5)));
+    assert!(sink.messages().is_empty());
+
+    // Time stands still while paused.
+    runtime.tick(0.5);
+    assert_eq!(runtime.time_since_start(), 0.5);
+
+    runtime.step();
+    assert_eq!(sink.messages(), vec!["first"]);
+    assert_eq!(runtime.paused().map(|a| a.node_id), Some(second_id));
+    runtime.resume();
+    assert_eq!(runtime.paused(), None);
+    assert_eq!(sink.messages(), vec!["first", "second"]);
+    sink.clear();
+
+    runtime.remove_breakpoint(&Breakpoint::Node(first_id));
+    runtime.add_breakpoint(Breakpoint::Flow {
+        node_id: first_id,
+        socket: "out".to_string(),
+    });
+    runtime.tick(0.5);
+    assert_eq!(runtime.paused().map(|a| a.node_id), Some(second_id));
+    assert_eq!(sink.messages(), vec!["first"]);
+    runtime.resume();
+    assert_eq!(sink.messages(), vec!["first", "second"]);
+    assert_eq!(runtime.time_since_start(), 1.0);
+
+    // Input received while paused does not take the outputs of the tick.
+    runtime.add_variable(VariableDeclaration {
+        name: "x".to_string(),
+        value_type: ValueType::Float,
+        value: Value::Float(0.0),
+    }).unwrap();
+    runtime.start_recording();
+    runtime.tick(0.5);
+    runtime.set_variable(0, Value::Float(1.0)).unwrap();
+    runtime.resume();
+    let recording = runtime.stop_recording().unwrap();
+    let logs = |index: usize| recording.inputs[index].outputs.logs.iter().map(|a| a.message.clone()).collect::<Vec<_>>();
+    assert_eq!(logs(0), vec!["first", "second"]);
+    assert!(logs(1).is_empty());
+}
+
+#[test]
+fn test_input_while_paused() {
+    use crate::{CollectingLogSink, Configuration, Graph, NodeRegistry, Runtime, Value, ValueType, VariableDeclaration};
+
+    // The paused flow reads the variable the host sets meanwhile.
+    let registry = NodeRegistry::builtin();
+    let mut graph = Graph::new();
+    graph.declarations.variables.push(VariableDeclaration {
+        name: "x".to_string(),
+        value_type: ValueType::Float,
+        value: Value::Float(0.0),
+    });
+    let build = |op: &str, node_id, configuration: Configuration| registry.build(op, node_id, &configuration, &graph.declarations).unwrap();
+    let tick = build("event/onTick", 0, Configuration::new());
+    let first = build("debug/log", 1, Configuration::new().with("message", "first"));
+    let second = build("debug/log", 2, Configuration::new().with("message", "x is {x}"));
+    let get = build("variable/get", 3, Configuration::new().with("variable", 0));
+    graph
+        .add_node(tick)
+        .add_node(first)
+        .add_node(second)
+        .add_node(get)
+        .link_flow(0, "out", 1, "in")
+        .link_flow(1, "out", 2, "in")
+        .link_value(3, "value", 2, "x");
+
+    let sink = CollectingLogSink::default();
+    let mut runtime = Runtime::load(&registry, &graph).unwrap();
+    runtime.set_log_sink(sink.clone());
+    runtime.add_breakpoint(Breakpoint::Node(2));
+    runtime.start_recording();
+    runtime.tick(0.5);
+    assert_eq!(runtime.paused().map(|a| a.node_id), Some(2));
+
+    // Host input waits for the paused tick, as it does in a replay.
+    runtime.set_variable(0, Value::Float(1.0)).unwrap();
+    assert!(runtime.write_pointer("/nodes/0/translation", Value::Float3([0.0; 3])).is_none());
+    assert_eq!(runtime.variable(0), Some(&Value::Float(0.0)));
+    runtime.resume();
+    assert_eq!(sink.messages(), vec!["first", "x is 0"]);
+    assert_eq!(runtime.variable(0), Some(&Value::Float(1.0)));
+
+    let recording = runtime.stop_recording().unwrap();
+    let mut replayed = Runtime::load(&registry, &graph).unwrap();
+    recording.replay(&mut replayed).unwrap();
+    assert_eq!(replayed.variable(0), Some(&Value::Float(1.0)));
+}
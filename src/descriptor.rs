@@ -0,0 +1,155 @@
+use serde::{Deserialize, Serialize};
+use crate::{Configuration, Node, NodeId, ValueType};
+
+/// How a node takes part in execution.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NodeKind {
+    /// Only computes output values from its inputs, whenever they are requested.
+    Pure,
+    /// Is activated through an input flow socket.
+    Flow,
+    /// Is activated by the runtime when something happens, not by other nodes.
+    Event,
+}
+
+/// An input or output value socket of a node type.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ValueSocketDescriptor {
+    pub name: String,
+    /// `None` for sockets whose type depends on the node's configuration or on
+    /// what they are linked to, such as the `value` of `variable/set`.
+    pub value_type: Option<ValueType>,
+}
+
+/// The type of a configuration entry of a node type.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ConfigurationType {
+    Bool,
+    Int,
+    IntArray,
+    String,
+    StringArray,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConfigurationDescriptor {
+    pub name: String,
+    pub configuration_type: ConfigurationType,
+}
+
+/// Everything known about a node type without creating a node of it: what the
+/// [`crate::NodeRegistry`] validates graphs against and what an editor lists.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NodeDescriptor {
+    pub op: String,
+    /// The part of `op` before the `/`, e.g. `math` for `math/add`.
+    pub category: String,
+    pub description: String,
+    pub kind: NodeKind,
+    pub input_value_sockets: Vec<ValueSocketDescriptor>,
+    pub output_value_sockets: Vec<ValueSocketDescriptor>,
+    pub input_flow_sockets: Vec<String>,
+    pub output_flow_sockets: Vec<String>,
+    pub configuration: Vec<ConfigurationDescriptor>,
+}
+
+impl NodeDescriptor {
+    /// A node type without sockets or configuration yet.
+    pub fn new(op: impl ToString, kind: NodeKind, description: impl ToString) -> Self {
+        let op = op.to_string();
+        Self {
+            category: op.split_once('/').map(|(a, _)| a).unwrap_or_default().to_string(),
+            op,
+            description: description.to_string(),
+            kind,
+            input_value_sockets: vec![],
+            output_value_sockets: vec![],
+            input_flow_sockets: vec![],
+            output_flow_sockets: vec![],
+            configuration: vec![],
+        }
+    }
+
+    pub fn input_value(mut self, name: impl ToString, value_type: ValueType) -> Self {
+        self.input_value_sockets.push(ValueSocketDescriptor {
+            name: name.to_string(),
+            value_type: Some(value_type),
+        });
+        self
+    }
+
+    /// An input value socket that takes any type.
+    pub fn input_any(mut self, name: impl ToString) -> Self {
+        self.input_value_sockets.push(ValueSocketDescriptor {
+            name: name.to_string(),
+            value_type: None,
+        });
+        self
+    }
+
+    pub fn output_value(mut self, name: impl ToString, value_type: ValueType) -> Self {
+        self.output_value_sockets.push(ValueSocketDescriptor {
+            name: name.to_string(),
+            value_type: Some(value_type),
+        });
+        self
+    }
+
+    /// An output value socket whose type is only known once a node is configured.
+    pub fn output_any(mut self, name: impl ToString) -> Self {
+        self.output_value_sockets.push(ValueSocketDescriptor {
+            name: name.to_string(),
+            value_type: None,
+        });
+        self
+    }
+
+    pub fn input_flow(mut self, name: impl ToString) -> Self {
+        self.input_flow_sockets.push(name.to_string());
+        self
+    }
+
+    pub fn output_flow(mut self, name: impl ToString) -> Self {
+        self.output_flow_sockets.push(name.to_string());
+        self
+    }
+
+    pub fn configuration(mut self, name: impl ToString, configuration_type: ConfigurationType) -> Self {
+        self.configuration.push(ConfigurationDescriptor {
+            name: name.to_string(),
+            configuration_type,
+        });
+        self
+    }
+
+    /// The glTF extension that defines the op, for vendor-prefixed categories
+    /// such as `ACME_audio` in `ACME_audio/play`. Ops of the core
+    /// specification, and those without a vendor prefix, have none.
+    pub fn extension(&self) -> Option<&str> {
+        let (vendor, name) = self.category.split_once('_')?;
+        let is_vendor = !vendor.is_empty() && vendor.chars().all(|a| a.is_ascii_uppercase() || a.is_ascii_digit());
+        (is_vendor && !name.is_empty()).then_some(self.category.as_str())
+    }
+
+    pub fn input_value_socket(&self, name: &str) -> Option<&ValueSocketDescriptor> {
+        self.input_value_sockets.iter().find(|a| a.name == name)
+    }
+
+    pub fn output_value_socket(&self, name: &str) -> Option<&ValueSocketDescriptor> {
+        self.output_value_sockets.iter().find(|a| a.name == name)
+    }
+
+    /// The sockets of a node of this type, without configuration.
+    pub fn node(&self, node_id: NodeId) -> Node {
+        Node {
+            node_id,
+            op: self.op.clone(),
+            configuration: Configuration::new(),
+            input_value_sockets: self.input_value_sockets.iter().map(|a| a.name.clone()).collect(),
+            input_flow_sockets: self.input_flow_sockets.clone(),
+            output_value_sockets: self.output_value_sockets.iter().map(|a| a.name.clone()).collect(),
+            output_flow_sockets: self.output_flow_sockets.clone(),
+            values: Default::default(),
+        }
+    }
+}
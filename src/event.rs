@@ -0,0 +1,439 @@
+use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use crate::{node, Configuration, ConfigurationError, Declarations, ExecutionContext, NodeId, Severity, Value, ValueType};
+
+/// Something the [`crate::Runtime`] delivers to every node.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RuntimeEvent {
+    Start,
+    Tick {
+        time_since_start: f32,
+        time_since_last_tick: f32,
+    },
+    Custom(CustomEvent),
+    /// `selected` was selected and the selection propagated to its ancestor
+    /// `target`, which is `selected` itself at first.
+    Select {
+        target: usize,
+        selected: usize,
+        controller: i32,
+        point: [f32; 3],
+        ray_origin: [f32; 3],
+    },
+    /// `controller` started hovering `hovered`, and with it `target`, one of
+    /// its ancestors or itself.
+    HoverIn {
+        target: usize,
+        hovered: usize,
+        controller: i32,
+    },
+    /// `controller` stopped hovering `hovered`, and with it `target`, one of
+    /// its ancestors or itself.
+    HoverOut {
+        target: usize,
+        hovered: usize,
+        controller: i32,
+    },
+}
+
+/// A custom event declared by the graph, with the typed values it carries.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EventDeclaration {
+    pub id: String,
+    pub values: Vec<EventValue>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EventValue {
+    pub name: String,
+    pub value_type: ValueType,
+}
+
+/// One occurrence of a custom event, sent either by the host or by `event/send`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CustomEvent {
+    pub event: usize,
+    pub values: HashMap<String, Value>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum EventError {
+    #[error("no event is declared at index {0}")]
+    UnknownEvent(usize),
+    #[error("event `{event}` has no value named `{name}`")]
+    UnknownValue { event: String, name: String },
+    #[error("event `{event}` is missing its value `{name}`")]
+    MissingValue { event: String, name: String },
+    #[error("value `{name}` of event `{event}` is {found:?}, expected {expected:?}")]
+    TypeMismatch { event: String, name: String, expected: ValueType, found: ValueType },
+}
+
+impl EventDeclaration {
+    /// Checks that `values` holds exactly the declared values, with their declared types.
+    pub fn check(&self, values: &HashMap<String, Value>) -> Result<(), EventError> {
+        for name in values.keys() {
+            if !self.values.iter().any(|a| &a.name == name) {
+                return Err(EventError::UnknownValue { event: self.id.clone(), name: name.clone() });
+            }
+        }
+        for EventValue { name, value_type } in &self.values {
+            let value = values.get(name)
+                .ok_or_else(|| EventError::MissingValue { event: self.id.clone(), name: name.clone() })?;
+            if value.value_type() != *value_type {
+                return Err(EventError::TypeMismatch {
+                    event: self.id.clone(),
+                    name: name.clone(),
+                    expected: *value_type,
+                    found: value.value_type(),
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The event an `event/send` or `event/receive` node is configured with,
+/// which has to be one of `declarations`.
+fn declared_event<'a>(configuration: &Configuration, declarations: &'a Declarations) -> Result<(usize, &'a EventDeclaration), ConfigurationError> {
+    let event = configuration.index("event")?.unwrap_or(0);
+    let declaration = declarations.events.get(event)
+        .ok_or_else(|| ConfigurationError::invalid("event", format!("no event is declared at index {event}")))?;
+    Ok((event, declaration))
+}
+
+/// `event/onStart`, activated once by [`crate::Runtime::start`].
+#[derive(Clone, Serialize, Deserialize)]
+pub struct OnStartNode {
+    node_id: NodeId,
+}
+
+impl OnStartNode {
+    pub fn new(node_id: NodeId) -> Self {
+        Self {
+            node_id,
+        }
+    }
+}
+
+node! {
+    OnStartNode {
+        op: "event/onStart",
+        kind: Event,
+        description: "Activated once when the runtime starts.",
+        output_flows: ["out"],
+        new_node: |node_id| Self::new(node_id),
+    }
+    impl NodeBehavior {
+        fn value(&mut self, _: &mut ExecutionContext) -> Option<()> {
+            Some(())
+        }
+
+        fn activate_input_node(&mut self, ctx: &mut ExecutionContext) -> Option<()> {
+            ctx.fire(self.node_id, "out")
+        }
+
+        fn handle_event(&mut self, ctx: &mut ExecutionContext, event: &RuntimeEvent) -> Option<()> {
+            match event {
+                RuntimeEvent::Start => ctx.fire(self.node_id, "out"),
+                _ => Some(()),
+            }
+        }
+    }
+}
+
+/// `event/onTick`, activated by every [`crate::Runtime::tick`].
+#[derive(Clone, Serialize, Deserialize)]
+pub struct OnTickNode {
+    node_id: NodeId,
+}
+
+impl OnTickNode {
+    pub fn new(node_id: NodeId) -> Self {
+        Self {
+            node_id,
+        }
+    }
+}
+
+node! {
+    OnTickNode {
+        op: "event/onTick",
+        kind: Event,
+        description: "Activated on every tick.",
+        output_values: {
+            set_time_since_start = "timeSinceStart": f32,
+            set_time_since_last_tick = "timeSinceLastTick": f32,
+        },
+        output_flows: ["out"],
+        new_node: |node_id| Self::new(node_id),
+    }
+    impl NodeBehavior {
+        fn value(&mut self, _: &mut ExecutionContext) -> Option<()> {
+            Some(())
+        }
+
+        fn activate_input_node(&mut self, ctx: &mut ExecutionContext) -> Option<()> {
+            ctx.fire(self.node_id, "out")
+        }
+
+        fn handle_event(&mut self, ctx: &mut ExecutionContext, event: &RuntimeEvent) -> Option<()> {
+            match event {
+                RuntimeEvent::Tick { time_since_start, time_since_last_tick } => {
+                    self.set_time_since_start(ctx, *time_since_start);
+                    self.set_time_since_last_tick(ctx, *time_since_last_tick);
+                    ctx.fire(self.node_id, "out")
+                }
+                _ => Some(()),
+            }
+        }
+    }
+}
+
+/// `event/send`, queues the custom event `event` with its values taken from the
+/// input value sockets of the same names.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct EventSendNode {
+    node_id: NodeId,
+    event: usize,
+    values: Vec<EventValue>,
+}
+
+impl EventSendNode {
+    pub fn new(node_id: NodeId, event: usize, declaration: &EventDeclaration) -> Self {
+        Self {
+            node_id,
+            event,
+            values: declaration.values.clone(),
+        }
+    }
+}
+
+node! {
+    EventSendNode {
+        op: "event/send",
+        kind: Flow,
+        description: "Sends a custom event with its values taken from the inputs of the same names.",
+        input_flows: ["in"],
+        output_flows: ["out"],
+        configuration: |node| { "event": Int(node.event as i32) },
+        new_node: |node_id, configuration, declarations| {
+            let (event, declaration) = declared_event(configuration, declarations)?;
+            Self::new(node_id, event, declaration)
+        },
+        layout: |node, descriptor| node.values.iter().fold(descriptor, |a, b| a.input_value(&b.name, b.value_type)),
+    }
+    impl NodeBehavior {
+        fn value(&mut self, _: &mut ExecutionContext) -> Option<()> {
+            panic!("cannot request value from this node");
+        }
+
+        fn activate_input_node(&mut self, ctx: &mut ExecutionContext) -> Option<()> {
+            let mut values = HashMap::new();
+            for EventValue { name, .. } in &self.values {
+                let Some(value) = ctx.input_value(self.node_id, name) else {
+                    ctx.log.log(Severity::Error, &format!("event/send node {} has no value for `{name}`, the event is not sent", self.node_id));
+                    return Some(());
+                };
+                values.insert(name.clone(), value);
+            }
+            ctx.events.push_back(CustomEvent {
+                event: self.event,
+                values,
+            });
+            ctx.fire(self.node_id, "out")
+        }
+    }
+}
+
+/// `event/receive`, activated whenever the runtime dispatches the custom event
+/// `event`, exposing its values as output value sockets.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct EventReceiveNode {
+    node_id: NodeId,
+    event: usize,
+    values: Vec<EventValue>,
+}
+
+impl EventReceiveNode {
+    pub fn new(node_id: NodeId, event: usize, declaration: &EventDeclaration) -> Self {
+        Self {
+            node_id,
+            event,
+            values: declaration.values.clone(),
+        }
+    }
+}
+
+node! {
+    EventReceiveNode {
+        op: "event/receive",
+        kind: Event,
+        description: "Activated whenever a custom event is received, exposing its values.",
+        output_flows: ["out"],
+        configuration: |node| { "event": Int(node.event as i32) },
+        new_node: |node_id, configuration, declarations| {
+            let (event, declaration) = declared_event(configuration, declarations)?;
+            Self::new(node_id, event, declaration)
+        },
+        layout: |node, descriptor| node.values.iter().fold(descriptor, |a, b| a.output_value(&b.name, b.value_type)),
+    }
+    impl NodeBehavior {
+        fn value(&mut self, _: &mut ExecutionContext) -> Option<()> {
+            Some(())
+        }
+
+        fn activate_input_node(&mut self, ctx: &mut ExecutionContext) -> Option<()> {
+            ctx.fire(self.node_id, "out")
+        }
+
+        fn handle_event(&mut self, ctx: &mut ExecutionContext, event: &RuntimeEvent) -> Option<()> {
+            match event {
+                RuntimeEvent::Custom(custom_event) if custom_event.event == self.event => {
+                    for (name, value) in &custom_event.values {
+                        ctx.existing_values.set_value(self.node_id, name, value.clone());
+                    }
+                    ctx.fire(self.node_id, "out")
+                }
+                _ => Some(()),
+            }
+        }
+    }
+}
+
+#[test]
+fn test_send_and_receive() {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use crate::{Graph, NodeBehavior, NodeBehaviors, Runtime};
+
+    let mut graph = Graph::new();
+    let mut node_behaviors = NodeBehaviors(HashMap::new());
+
+    let ping = EventDeclaration {
+        id: "ping".to_string(),
+        values: vec![EventValue { name: "x".to_string(), value_type: ValueType::Float }],
+    };
+    let pong = EventDeclaration {
+        id: "pong".to_string(),
+        values: vec![EventValue { name: "y".to_string(), value_type: ValueType::Float }],
+    };
+
+    let receive_id = 0;
+    let send_id = 1;
+    let receive = EventReceiveNode::new(receive_id, 0, &ping);
+    let send = EventSendNode::new(send_id, 1, &pong);
+
+    graph
+        .add_node(receive.node())
+        .add_node(send.node())
+        .link_flow(receive_id, "out", send_id, "in")
+        .link_value(receive_id, "x", send_id, "y");
+    node_behaviors.add(Box::new(receive));
+    node_behaviors.add(Box::new(send));
+
+    let mut runtime = Runtime::new(graph.resolve().unwrap(), node_behaviors);
+    assert_eq!(runtime.add_event(ping), 0);
+    assert_eq!(runtime.add_event(pong), 1);
+
+    let received = Rc::new(RefCell::new(vec![]));
+    let received_clone = received.clone();
+    runtime.subscribe(1, move |event| received_clone.borrow_mut().push(event.clone()));
+
+    assert!(runtime.send_event(0, HashMap::from([("x".to_string(), Value::Int(1))])).is_err());
+    runtime.send_event(0, HashMap::from([("x".to_string(), Value::Float(2.0))])).unwrap();
+    runtime.tick(0.1);
+
+    assert_eq!(*received.borrow(), vec![CustomEvent {
+        event: 1,
+        values: HashMap::from([("y".to_string(), Value::Float(2.0))]),
+    }]);
+}
+
+#[test]
+fn test_send_mismatch() {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use crate::{CollectingLogSink, Graph, MathPi, NodeBehavior, NodeBehaviors, OnStartNode, Runtime};
+
+    // The graph sends a float where the event declares an int.
+    let ping = EventDeclaration {
+        id: "ping".to_string(),
+        values: vec![EventValue { name: "x".to_string(), value_type: ValueType::Int }],
+    };
+    let mut graph = Graph::new();
+    let mut node_behaviors = NodeBehaviors(HashMap::new());
+    let start = OnStartNode::new(0);
+    let send = EventSendNode::new(1, 0, &ping);
+    let pi = MathPi::new(2);
+    graph
+        .add_node(start.node())
+        .add_node(send.node())
+        .add_node(pi.node())
+        .link_flow(0, "out", 1, "in")
+        .link_value(2, "value", 1, "x");
+    node_behaviors.add(Box::new(start));
+    node_behaviors.add(Box::new(send));
+    node_behaviors.add(Box::new(pi));
+
+    let sink = CollectingLogSink::default();
+    let mut runtime = Runtime::new(graph.resolve().unwrap(), node_behaviors);
+    runtime.set_log_sink(sink.clone());
+    runtime.add_event(ping);
+    let received = Rc::new(RefCell::new(vec![]));
+    let received_clone = received.clone();
+    runtime.subscribe(0, move |event| received_clone.borrow_mut().push(event.clone()));
+    runtime.start();
+    runtime.tick(0.1);
+    assert!(received.borrow().is_empty());
+    assert_eq!(sink.messages(), vec!["value `x` of event `ping` is Float, expected Int"]);
+}
+
+#[test]
+fn test_load_send_and_receive() {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use crate::{Graph, LoadError, NodeRegistry, Runtime};
+
+    // The payload sockets come from the declared events, through the
+    // registry, serialization and the loader.
+    let registry = NodeRegistry::builtin();
+    let mut graph = Graph::new();
+    graph.declarations.events = vec![
+        EventDeclaration {
+            id: "ping".to_string(),
+            values: vec![EventValue { name: "x".to_string(), value_type: ValueType::Float }],
+        },
+        EventDeclaration {
+            id: "pong".to_string(),
+            values: vec![EventValue { name: "y".to_string(), value_type: ValueType::Float }],
+        },
+    ];
+    let receive = registry.build("event/receive", 0, &Configuration::new().with("event", 0), &graph.declarations).unwrap();
+    let send = registry.build("event/send", 1, &Configuration::new().with("event", 1), &graph.declarations).unwrap();
+    assert_eq!(receive.output_value_sockets, vec!["x"]);
+    assert_eq!(send.input_value_sockets, vec!["y"]);
+    graph
+        .add_node(receive)
+        .add_node(send)
+        .link_flow(0, "out", 1, "in")
+        .link_value(0, "x", 1, "y");
+    let graph = serde_json::from_str::<Graph>(&serde_json::to_string(&graph).unwrap()).unwrap();
+
+    let mut runtime = Runtime::load(&registry, &graph).unwrap();
+    let received = Rc::new(RefCell::new(vec![]));
+    let received_clone = received.clone();
+    runtime.subscribe(1, move |event| received_clone.borrow_mut().push(event.clone()));
+    runtime.send_event(0, HashMap::from([("x".to_string(), Value::Float(2.0))])).unwrap();
+    runtime.tick(0.1);
+    assert_eq!(*received.borrow(), vec![CustomEvent {
+        event: 1,
+        values: HashMap::from([("y".to_string(), Value::Float(2.0))]),
+    }]);
+
+    let mut broken = graph.clone();
+    broken.declarations.events[1].values[0].value_type = ValueType::Int;
+    assert!(matches!(registry.load(&broken), Err(LoadError::TypeMismatch { node_id: 1, .. })));
+    let mut broken = graph;
+    broken.nodes[1].configuration.set("event", 2);
+    assert!(matches!(registry.load(&broken), Err(LoadError::Configuration { node_id: 1, .. })));
+}
@@ -0,0 +1,986 @@
+mod animation;
+mod buffer;
+mod interactivity;
+
+use serde::{Deserialize, Serialize};
+use crate::{Value, World, WorldEvent};
+use buffer::BufferData;
+
+pub use animation::{Animation, Channel, ChannelPath, Playback, Sampler, SamplerInterpolation};
+pub use interactivity::{read_graph, write_graph, InteractivityError};
+
+#[derive(Debug, thiserror::Error)]
+pub enum GltfError {
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    #[error("invalid binary glTF: {0}")]
+    Glb(String),
+    #[error("buffer {0} is neither embedded nor the binary chunk")]
+    Buffer(usize),
+    #[error("accessor {0} cannot be read")]
+    Accessor(usize),
+    #[error("animation {0} is invalid")]
+    Animation(usize),
+}
+
+/// A glTF document held in memory, for running graphs without an engine.
+/// Pointers read and write its nodes, meshes, materials, cameras and
+/// `KHR_lights_punctual` lights, along with the `KHR_node_selectability`,
+/// `KHR_node_hoverability` and `KHR_node_visibility` flags of its nodes.
+/// Properties missing from the document read as their glTF defaults, except
+/// optional ones without a default such as `/cameras/{}/perspective/zfar`,
+/// which do not resolve until written.
+///
+/// Animations are sampled from their accessor data and played back by
+/// [`World::advance`], which poses node TRS and morph weights and reports
+/// finished playback to the runtime.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GltfWorld {
+    pub nodes: Vec<GltfNode>,
+    pub meshes: Vec<GltfMesh>,
+    pub materials: Vec<GltfMaterial>,
+    pub cameras: Vec<GltfCamera>,
+    pub extensions: GltfExtensions,
+    #[serde(skip)]
+    pub animations: Vec<Animation>,
+    #[serde(skip)]
+    pub playing: Vec<Playback>,
+    #[serde(skip)]
+    events: Vec<WorldEvent>,
+}
+
+/// A node's transform is always held as TRS. A `matrix` from the document is
+/// decomposed on load and `/nodes/{}/matrix` is composed from TRS when read.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GltfNode {
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mesh: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub matrix: Option<[f32; 16]>,
+    pub translation: [f32; 3],
+    pub rotation: [f32; 4],
+    pub scale: [f32; 3],
+    /// Overrides the weights of the mesh when present.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub weights: Option<Vec<f32>>,
+    #[serde(skip_serializing_if = "NodeExtensions::is_empty")]
+    pub extensions: NodeExtensions,
+}
+
+impl Default for GltfNode {
+    fn default() -> Self {
+        Self {
+            children: vec![],
+            mesh: None,
+            matrix: None,
+            translation: [0.0; 3],
+            rotation: [0.0, 0.0, 0.0, 1.0],
+            scale: [1.0; 3],
+            weights: None,
+            extensions: NodeExtensions::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NodeExtensions {
+    #[serde(rename = "KHR_node_selectability", skip_serializing_if = "Option::is_none")]
+    pub khr_node_selectability: Option<NodeSelectability>,
+    #[serde(rename = "KHR_node_hoverability", skip_serializing_if = "Option::is_none")]
+    pub khr_node_hoverability: Option<NodeHoverability>,
+    #[serde(rename = "KHR_node_visibility", skip_serializing_if = "Option::is_none")]
+    pub khr_node_visibility: Option<NodeVisibility>,
+}
+
+impl NodeExtensions {
+    fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NodeSelectability {
+    pub selectable: bool,
+}
+
+impl Default for NodeSelectability {
+    fn default() -> Self {
+        Self {
+            selectable: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NodeHoverability {
+    pub hoverable: bool,
+}
+
+impl Default for NodeHoverability {
+    fn default() -> Self {
+        Self {
+            hoverable: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NodeVisibility {
+    pub visible: bool,
+}
+
+impl Default for NodeVisibility {
+    fn default() -> Self {
+        Self {
+            visible: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GltfMesh {
+    pub weights: Vec<f32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct GltfMaterial {
+    pub pbr_metallic_roughness: PbrMetallicRoughness,
+    pub emissive_factor: [f32; 3],
+    pub alpha_cutoff: f32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub normal_texture: Option<NormalTextureInfo>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub occlusion_texture: Option<OcclusionTextureInfo>,
+}
+
+impl Default for GltfMaterial {
+    fn default() -> Self {
+        Self {
+            pbr_metallic_roughness: PbrMetallicRoughness::default(),
+            emissive_factor: [0.0; 3],
+            alpha_cutoff: 0.5,
+            normal_texture: None,
+            occlusion_texture: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct PbrMetallicRoughness {
+    pub base_color_factor: [f32; 4],
+    pub metallic_factor: f32,
+    pub roughness_factor: f32,
+}
+
+impl Default for PbrMetallicRoughness {
+    fn default() -> Self {
+        Self {
+            base_color_factor: [1.0; 4],
+            metallic_factor: 1.0,
+            roughness_factor: 1.0,
+        }
+    }
+}
+
+/// Only the part of the texture reference pointers can reach.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NormalTextureInfo {
+    pub index: usize,
+    pub scale: f32,
+}
+
+impl Default for NormalTextureInfo {
+    fn default() -> Self {
+        Self {
+            index: 0,
+            scale: 1.0,
+        }
+    }
+}
+
+/// Only the part of the texture reference pointers can reach.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OcclusionTextureInfo {
+    pub index: usize,
+    pub strength: f32,
+}
+
+impl Default for OcclusionTextureInfo {
+    fn default() -> Self {
+        Self {
+            index: 0,
+            strength: 1.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GltfCamera {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub perspective: Option<Perspective>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub orthographic: Option<Orthographic>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Perspective {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub aspect_ratio: Option<f32>,
+    pub yfov: f32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub zfar: Option<f32>,
+    pub znear: f32,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Orthographic {
+    pub xmag: f32,
+    pub ymag: f32,
+    pub zfar: f32,
+    pub znear: f32,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GltfExtensions {
+    #[serde(rename = "KHR_lights_punctual", skip_serializing_if = "Option::is_none")]
+    pub khr_lights_punctual: Option<LightsPunctual>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LightsPunctual {
+    pub lights: Vec<Light>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Light {
+    #[serde(rename = "type")]
+    pub light_type: String,
+    pub color: [f32; 3],
+    pub intensity: f32,
+    /// Unlimited when absent.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub range: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub spot: Option<Spot>,
+}
+
+impl Default for Light {
+    fn default() -> Self {
+        Self {
+            light_type: "point".to_string(),
+            color: [1.0; 3],
+            intensity: 1.0,
+            range: None,
+            spot: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Spot {
+    pub inner_cone_angle: f32,
+    pub outer_cone_angle: f32,
+}
+
+impl Default for Spot {
+    fn default() -> Self {
+        Self {
+            inner_cone_angle: 0.0,
+            outer_cone_angle: std::f32::consts::FRAC_PI_4,
+        }
+    }
+}
+
+impl GltfWorld {
+    /// Reads the parts of a glTF JSON document pointers and animations can
+    /// reach, ignoring the rest. Buffers must be embedded as data URIs.
+    pub fn from_json(json: &str) -> Result<Self, GltfError> {
+        Self::load(json, None)
+    }
+
+    /// Like [`Self::from_json`] for a binary glTF, whose first buffer may be
+    /// its binary chunk.
+    pub fn from_glb(bytes: &[u8]) -> Result<Self, GltfError> {
+        let (json, bin) = buffer::split_glb(bytes)?;
+        Self::load(json, bin)
+    }
+
+    fn load(json: &str, bin: Option<&[u8]>) -> Result<Self, GltfError> {
+        let mut world: Self = serde_json::from_str(json)?;
+        for node in &mut world.nodes {
+            if let Some(matrix) = node.matrix.take() {
+                (node.translation, node.rotation, node.scale) = decompose(&matrix);
+            }
+        }
+        let animations = serde_json::from_str::<animation::AnimationData>(json)?.animations;
+        if !animations.is_empty() {
+            let data = serde_json::from_str::<BufferData>(json)?;
+            let buffers = data.load_buffers(bin)?;
+            world.animations = animations.iter()
+                .enumerate()
+                .map(|(i, animation)| Animation::load(i, animation, &data, &buffers))
+                .collect::<Result<_, _>>()?;
+        }
+        Ok(world)
+    }
+
+    fn lights(&self) -> Option<&Vec<Light>> {
+        Some(&self.extensions.khr_lights_punctual.as_ref()?.lights)
+    }
+
+    fn lights_mut(&mut self) -> Option<&mut Vec<Light>> {
+        Some(&mut self.extensions.khr_lights_punctual.as_mut()?.lights)
+    }
+
+    /// Whether a node is rendered: hiding a node with `KHR_node_visibility`
+    /// hides all of its descendants too.
+    pub fn is_visible(&self, node: usize) -> bool {
+        let mut visited = vec![];
+        let mut node = Some(node);
+        while let Some(current) = node.filter(|a| !visited.contains(a)) {
+            if !self.visible(current) {
+                return false;
+            }
+            visited.push(current);
+            node = self.parent(current);
+        }
+        true
+    }
+
+    /// The morph weights a node renders with: its own, or else its mesh's.
+    fn node_weights(&self, node: &GltfNode) -> Option<Vec<f32>> {
+        match &node.weights {
+            Some(weights) => Some(weights.clone()),
+            None => Some(self.meshes.get(node.mesh?)?.weights.clone()),
+        }
+    }
+}
+
+fn index(segment: &str) -> Option<usize> {
+    segment.parse().ok()
+}
+
+fn float(value: Value) -> Option<f32> {
+    match value {
+        Value::Float(value) => Some(value),
+        _ => None,
+    }
+}
+
+fn float3(value: Value) -> Option<[f32; 3]> {
+    match value {
+        Value::Float3(value) => Some(value),
+        _ => None,
+    }
+}
+
+fn float4(value: Value) -> Option<[f32; 4]> {
+    match value {
+        Value::Float4(value) => Some(value),
+        _ => None,
+    }
+}
+
+impl World for GltfWorld {
+    fn read_pointer(&self, pointer: &str) -> Option<Value> {
+        let segments = pointer.strip_prefix('/')?.split('/').collect::<Vec<_>>();
+        let value = match segments.as_slice() {
+            ["nodes", node, property @ ..] => {
+                let node = self.nodes.get(index(node)?)?;
+                match property {
+                    ["translation"] => Value::Float3(node.translation),
+                    ["rotation"] => Value::Float4(node.rotation),
+                    ["scale"] => Value::Float3(node.scale),
+                    ["matrix"] => Value::Float4x4(compose(node.translation, node.rotation, node.scale)),
+                    ["weights", weight] => Value::Float(*self.node_weights(node)?.get(index(weight)?)?),
+                    ["extensions", "KHR_node_selectability", "selectable"] => Value::Bool(node.extensions.khr_node_selectability.clone().unwrap_or_default().selectable),
+                    ["extensions", "KHR_node_hoverability", "hoverable"] => Value::Bool(node.extensions.khr_node_hoverability.clone().unwrap_or_default().hoverable),
+                    ["extensions", "KHR_node_visibility", "visible"] => Value::Bool(node.extensions.khr_node_visibility.clone().unwrap_or_default().visible),
+                    _ => return None,
+                }
+            }
+            ["meshes", mesh, "weights", weight] => Value::Float(*self.meshes.get(index(mesh)?)?.weights.get(index(weight)?)?),
+            ["materials", material, property @ ..] => {
+                let material = self.materials.get(index(material)?)?;
+                match property {
+                    ["pbrMetallicRoughness", "baseColorFactor"] => Value::Float4(material.pbr_metallic_roughness.base_color_factor),
+                    ["pbrMetallicRoughness", "metallicFactor"] => Value::Float(material.pbr_metallic_roughness.metallic_factor),
+                    ["pbrMetallicRoughness", "roughnessFactor"] => Value::Float(material.pbr_metallic_roughness.roughness_factor),
+                    ["emissiveFactor"] => Value::Float3(material.emissive_factor),
+                    ["alphaCutoff"] => Value::Float(material.alpha_cutoff),
+                    ["normalTexture", "scale"] => Value::Float(material.normal_texture.as_ref()?.scale),
+                    ["occlusionTexture", "strength"] => Value::Float(material.occlusion_texture.as_ref()?.strength),
+                    _ => return None,
+                }
+            }
+            ["cameras", camera, property @ ..] => {
+                let camera = self.cameras.get(index(camera)?)?;
+                match property {
+                    ["perspective", property] => {
+                        let perspective = camera.perspective.as_ref()?;
+                        Value::Float(match *property {
+                            "aspectRatio" => perspective.aspect_ratio?,
+                            "yfov" => perspective.yfov,
+                            "zfar" => perspective.zfar?,
+                            "znear" => perspective.znear,
+                            _ => return None,
+                        })
+                    }
+                    ["orthographic", property] => {
+                        let orthographic = camera.orthographic.as_ref()?;
+                        Value::Float(match *property {
+                            "xmag" => orthographic.xmag,
+                            "ymag" => orthographic.ymag,
+                            "zfar" => orthographic.zfar,
+                            "znear" => orthographic.znear,
+                            _ => return None,
+                        })
+                    }
+                    _ => return None,
+                }
+            }
+            ["extensions", "KHR_lights_punctual", "lights", light, property @ ..] => {
+                let light = self.lights()?.get(index(light)?)?;
+                match property {
+                    ["color"] => Value::Float3(light.color),
+                    ["intensity"] => Value::Float(light.intensity),
+                    ["range"] => Value::Float(light.range?),
+                    ["spot", "innerConeAngle"] => Value::Float(light.spot.as_ref()?.inner_cone_angle),
+                    ["spot", "outerConeAngle"] => Value::Float(light.spot.as_ref()?.outer_cone_angle),
+                    _ => return None,
+                }
+            }
+            _ => return None,
+        };
+        Some(value)
+    }
+
+    /// `/nodes/{}/matrix` cannot be written, since it is derived from TRS.
+    fn write_pointer(&mut self, pointer: &str, value: Value) -> Option<()> {
+        let segments = pointer.strip_prefix('/')?.split('/').collect::<Vec<_>>();
+        match segments.as_slice() {
+            ["nodes", node, property @ ..] => {
+                let node = index(node)?;
+                match property {
+                    ["translation"] => self.nodes.get_mut(node)?.translation = float3(value)?,
+                    ["rotation"] => self.nodes.get_mut(node)?.rotation = float4(value)?,
+                    ["scale"] => self.nodes.get_mut(node)?.scale = float3(value)?,
+                    ["weights", weight] => {
+                        let mut weights = self.node_weights(self.nodes.get(node)?)?;
+                        *weights.get_mut(index(weight)?)? = float(value)?;
+                        self.nodes[node].weights = Some(weights);
+                    }
+                    ["extensions", "KHR_node_selectability", "selectable"] => {
+                        let Value::Bool(selectable) = value else {
+                            return None;
+                        };
+                        self.nodes.get_mut(node)?.extensions.khr_node_selectability = Some(NodeSelectability { selectable });
+                    }
+                    ["extensions", "KHR_node_hoverability", "hoverable"] => {
+                        let Value::Bool(hoverable) = value else {
+                            return None;
+                        };
+                        self.nodes.get_mut(node)?.extensions.khr_node_hoverability = Some(NodeHoverability { hoverable });
+                    }
+                    ["extensions", "KHR_node_visibility", "visible"] => {
+                        let Value::Bool(visible) = value else {
+                            return None;
+                        };
+                        self.nodes.get_mut(node)?.extensions.khr_node_visibility = Some(NodeVisibility { visible });
+                    }
+                    _ => return None,
+                }
+            }
+            ["meshes", mesh, "weights", weight] => *self.meshes.get_mut(index(mesh)?)?.weights.get_mut(index(weight)?)? = float(value)?,
+            ["materials", material, property @ ..] => {
+                let material = self.materials.get_mut(index(material)?)?;
+                match property {
+                    ["pbrMetallicRoughness", "baseColorFactor"] => material.pbr_metallic_roughness.base_color_factor = float4(value)?,
+                    ["pbrMetallicRoughness", "metallicFactor"] => material.pbr_metallic_roughness.metallic_factor = float(value)?,
+                    ["pbrMetallicRoughness", "roughnessFactor"] => material.pbr_metallic_roughness.roughness_factor = float(value)?,
+                    ["emissiveFactor"] => material.emissive_factor = float3(value)?,
+                    ["alphaCutoff"] => material.alpha_cutoff = float(value)?,
+                    ["normalTexture", "scale"] => material.normal_texture.as_mut()?.scale = float(value)?,
+                    ["occlusionTexture", "strength"] => material.occlusion_texture.as_mut()?.strength = float(value)?,
+                    _ => return None,
+                }
+            }
+            ["cameras", camera, property @ ..] => {
+                let camera = self.cameras.get_mut(index(camera)?)?;
+                match property {
+                    ["perspective", property] => {
+                        let perspective = camera.perspective.as_mut()?;
+                        let value = float(value)?;
+                        match *property {
+                            "aspectRatio" => perspective.aspect_ratio = Some(value),
+                            "yfov" => perspective.yfov = value,
+                            "zfar" => perspective.zfar = Some(value),
+                            "znear" => perspective.znear = value,
+                            _ => return None,
+                        }
+                    }
+                    ["orthographic", property] => {
+                        let orthographic = camera.orthographic.as_mut()?;
+                        let value = float(value)?;
+                        match *property {
+                            "xmag" => orthographic.xmag = value,
+                            "ymag" => orthographic.ymag = value,
+                            "zfar" => orthographic.zfar = value,
+                            "znear" => orthographic.znear = value,
+                            _ => return None,
+                        }
+                    }
+                    _ => return None,
+                }
+            }
+            ["extensions", "KHR_lights_punctual", "lights", light, property @ ..] => {
+                let light = self.lights_mut()?.get_mut(index(light)?)?;
+                match property {
+                    ["color"] => light.color = float3(value)?,
+                    ["intensity"] => light.intensity = float(value)?,
+                    ["range"] => light.range = Some(float(value)?),
+                    ["spot", "innerConeAngle"] => light.spot.as_mut()?.inner_cone_angle = float(value)?,
+                    ["spot", "outerConeAngle"] => light.spot.as_mut()?.outer_cone_angle = float(value)?,
+                    _ => return None,
+                }
+            }
+            _ => return None,
+        }
+        Some(())
+    }
+
+    fn parent(&self, node: usize) -> Option<usize> {
+        self.nodes.iter().position(|a| a.children.contains(&node))
+    }
+
+    fn selectable(&self, node: usize) -> bool {
+        self.nodes.get(node)
+            .and_then(|a| a.extensions.khr_node_selectability.as_ref())
+            .is_none_or(|a| a.selectable)
+    }
+
+    fn hoverable(&self, node: usize) -> bool {
+        self.nodes.get(node)
+            .and_then(|a| a.extensions.khr_node_hoverability.as_ref())
+            .is_none_or(|a| a.hoverable)
+    }
+
+    fn visible(&self, node: usize) -> bool {
+        self.nodes.get(node)
+            .and_then(|a| a.extensions.khr_node_visibility.as_ref())
+            .is_none_or(|a| a.visible)
+    }
+
+    /// Restarts the animation if it is already playing.
+    fn start_animation(&mut self, animation: i32, start_time: f32, end_time: f32, speed: f32) -> Option<()> {
+        let animation = usize::try_from(animation).ok()?;
+        self.animations.get(animation)?.apply(start_time, &mut self.nodes);
+        self.playing.retain(|a| a.animation != animation);
+        self.playing.push(Playback {
+            animation,
+            time: start_time,
+            end_time,
+            speed,
+            stop_time: None,
+        });
+        Some(())
+    }
+
+    fn stop_animation(&mut self, animation: i32) -> Option<()> {
+        let animation = usize::try_from(animation).ok()?;
+        self.animations.get(animation)?;
+        self.playing.retain(|a| a.animation != animation);
+        Some(())
+    }
+
+    /// Fails if the animation is not playing.
+    fn stop_animation_at(&mut self, animation: i32, stop_time: f32) -> Option<()> {
+        let animation = usize::try_from(animation).ok()?;
+        self.playing.iter_mut().find(|a| a.animation == animation)?.stop_time = Some(stop_time);
+        Some(())
+    }
+
+    fn advance(&mut self, dt: f32) {
+        let mut finished = vec![];
+        for playback in &mut self.playing {
+            let done = playback.advance(dt);
+            let animation = &self.animations[playback.animation];
+            animation.apply(playback.animation_time(animation.duration), &mut self.nodes);
+            if done {
+                finished.push(playback.animation);
+            }
+        }
+        self.playing.retain(|a| !finished.contains(&a.animation));
+        self.events.extend(finished.into_iter().map(|a| WorldEvent::AnimationCompleted(a as i32)));
+    }
+
+    fn take_events(&mut self) -> Vec<WorldEvent> {
+        std::mem::take(&mut self.events)
+    }
+}
+
+/// The column-major matrix of a translation, rotation quaternion and scale.
+pub fn compose(translation: [f32; 3], rotation: [f32; 4], scale: [f32; 3]) -> [f32; 16] {
+    let [x, y, z, w] = rotation;
+    let [sx, sy, sz] = scale;
+    let [tx, ty, tz] = translation;
+    [
+        (1.0 - 2.0 * (y * y + z * z)) * sx, 2.0 * (x * y + z * w) * sx, 2.0 * (x * z - y * w) * sx, 0.0,
+        2.0 * (x * y - z * w) * sy, (1.0 - 2.0 * (x * x + z * z)) * sy, 2.0 * (y * z + x * w) * sy, 0.0,
+        2.0 * (x * z + y * w) * sz, 2.0 * (y * z - x * w) * sz, (1.0 - 2.0 * (x * x + y * y)) * sz, 0.0,
+        tx, ty, tz, 1.0,
+    ]
+}
+
+/// The translation, rotation quaternion and scale of a column-major matrix
+/// without shear. A negative determinant is folded into the x scale.
+pub fn decompose(matrix: &[f32; 16]) -> ([f32; 3], [f32; 4], [f32; 3]) {
+    let column = |i: usize| [matrix[i * 4], matrix[i * 4 + 1], matrix[i * 4 + 2]];
+    let length = |a: [f32; 3]| (a[0] * a[0] + a[1] * a[1] + a[2] * a[2]).sqrt();
+    let (c0, c1, c2) = (column(0), column(1), column(2));
+    let determinant = c0[0] * (c1[1] * c2[2] - c2[1] * c1[2]) - c1[0] * (c0[1] * c2[2] - c2[1] * c0[2]) + c2[0] * (c0[1] * c1[2] - c1[1] * c0[2]);
+    let mut scale = [length(c0), length(c1), length(c2)];
+    if determinant < 0.0 {
+        scale[0] = -scale[0];
+    }
+    // r(row, column) of the rotation matrix
+    let r = |row: usize, col: usize| {
+        if scale[col] == 0.0 {
+            0.0
+        } else {
+            matrix[col * 4 + row] / scale[col]
+        }
+    };
+    let trace = r(0, 0) + r(1, 1) + r(2, 2);
+    let rotation = if trace > 0.0 {
+        let s = 0.5 / (trace + 1.0).sqrt();
+        [(r(2, 1) - r(1, 2)) * s, (r(0, 2) - r(2, 0)) * s, (r(1, 0) - r(0, 1)) * s, 0.25 / s]
+    } else if r(0, 0) > r(1, 1) && r(0, 0) > r(2, 2) {
+        let s = 2.0 * (1.0 + r(0, 0) - r(1, 1) - r(2, 2)).sqrt();
+        [0.25 * s, (r(0, 1) + r(1, 0)) / s, (r(0, 2) + r(2, 0)) / s, (r(2, 1) - r(1, 2)) / s]
+    } else if r(1, 1) > r(2, 2) {
+        let s = 2.0 * (1.0 + r(1, 1) - r(0, 0) - r(2, 2)).sqrt();
+        [(r(0, 1) + r(1, 0)) / s, 0.25 * s, (r(1, 2) + r(2, 1)) / s, (r(0, 2) - r(2, 0)) / s]
+    } else {
+        let s = 2.0 * (1.0 + r(2, 2) - r(0, 0) - r(1, 1)).sqrt();
+        [(r(0, 2) + r(2, 0)) / s, (r(1, 2) + r(2, 1)) / s, 0.25 * s, (r(1, 0) - r(0, 1)) / s]
+    };
+    ([matrix[12], matrix[13], matrix[14]], rotation, scale)
+}
+
+#[test]
+fn test_gltf_world() {
+    use crate::pointer::{PointerTemplate, POINTER_PROPERTIES};
+
+    let mut world = GltfWorld::from_json(r#"{
+        "asset": { "version": "2.0" },
+        "nodes": [
+            { "mesh": 0, "children": [1] },
+            { "matrix": [0, 1, 0, 0, -2, 0, 0, 0, 0, 0, 2, 0, 1, 2, 3, 1] }
+        ],
+        "meshes": [{ "primitives": [], "weights": [0.25, 0.5] }],
+        "materials": [{
+            "pbrMetallicRoughness": { "metallicFactor": 0.0 },
+            "normalTexture": { "index": 0 },
+            "occlusionTexture": { "index": 0, "strength": 0.5 }
+        }],
+        "cameras": [
+            { "type": "perspective", "perspective": { "aspectRatio": 1.5, "yfov": 0.8, "zfar": 100, "znear": 0.1 } },
+            { "type": "orthographic", "orthographic": { "xmag": 1, "ymag": 1, "zfar": 10, "znear": 0 } }
+        ],
+        "extensions": {
+            "KHR_lights_punctual": { "lights": [{ "type": "spot", "range": 10, "spot": {} }] }
+        }
+    }"#).unwrap();
+
+    // Every property of the pointer table resolves against a document that has it.
+    for property in POINTER_PROPERTIES {
+        let pointer = PointerTemplate::parse(&property.template.replace("{}", "0")).unwrap().as_str().to_string();
+        let pointer = pointer.replacen("/cameras/0/orthographic", "/cameras/1/orthographic", 1);
+        assert_eq!(world.read_pointer(&pointer).map(|a| a.value_type()), Some(property.value_type), "{pointer}");
+    }
+
+    let (translation, rotation, scale) = (world.nodes[1].translation, world.nodes[1].rotation, world.nodes[1].scale);
+    assert_eq!(translation, [1.0, 2.0, 3.0]);
+    assert_eq!(scale, [1.0, 2.0, 2.0]);
+    let half = std::f32::consts::FRAC_1_SQRT_2;
+    assert!(rotation.iter().zip([0.0, 0.0, half, half]).all(|(a, b)| (a - b).abs() < 1e-6));
+    let matrix = compose(translation, rotation, scale);
+    assert!(matrix.iter().zip([0.0, 1.0, 0.0, 0.0, -2.0, 0.0, 0.0, 0.0, 0.0, 0.0, 2.0, 0.0, 1.0, 2.0, 3.0, 1.0]).all(|(a, b)| (a - b).abs() < 1e-6));
+
+    assert_eq!(world.read_pointer("/nodes/0/weights/1"), Some(Value::Float(0.5)));
+    world.write_pointer("/nodes/0/weights/1", Value::Float(1.0)).unwrap();
+    assert_eq!(world.read_pointer("/nodes/0/weights/1"), Some(Value::Float(1.0)));
+    assert_eq!(world.read_pointer("/meshes/0/weights/1"), Some(Value::Float(0.5)));
+    assert_eq!(world.read_pointer("/nodes/0/weights/2"), None);
+
+    world.write_pointer("/nodes/0/translation", Value::Float3([4.0, 5.0, 6.0])).unwrap();
+    assert_eq!(world.read_pointer("/nodes/0/matrix").unwrap(), Value::Float4x4(compose([4.0, 5.0, 6.0], [0.0, 0.0, 0.0, 1.0], [1.0; 3])));
+    assert_eq!(world.write_pointer("/nodes/0/matrix", Value::Float4x4([0.0; 16])), None);
+    assert_eq!(world.write_pointer("/nodes/0/translation", Value::Float(1.0)), None);
+    assert_eq!(world.write_pointer("/nodes/2/translation", Value::Float3([0.0; 3])), None);
+
+    assert_eq!(world.read_pointer("/materials/0/pbrMetallicRoughness/metallicFactor"), Some(Value::Float(0.0)));
+    assert_eq!(world.read_pointer("/materials/0/pbrMetallicRoughness/baseColorFactor"), Some(Value::Float4([1.0; 4])));
+    assert_eq!(world.read_pointer("/materials/0/occlusionTexture/strength"), Some(Value::Float(0.5)));
+    world.write_pointer("/cameras/0/perspective/yfov", Value::Float(1.0)).unwrap();
+    assert_eq!(world.read_pointer("/cameras/0/perspective/yfov"), Some(Value::Float(1.0)));
+    assert_eq!(world.read_pointer("/cameras/1/perspective/yfov"), None);
+    world.write_pointer("/extensions/KHR_lights_punctual/lights/0/intensity", Value::Float(3.0)).unwrap();
+    assert_eq!(world.read_pointer("/extensions/KHR_lights_punctual/lights/0/intensity"), Some(Value::Float(3.0)));
+    assert_eq!(world.read_pointer("/extensions/KHR_lights_punctual/lights/0/spot/outerConeAngle"), Some(Value::Float(std::f32::consts::FRAC_PI_4)));
+}
+
+#[test]
+fn test_gltf_animation() {
+    use std::collections::HashMap;
+    use crate::{AnimationStartNode, CollectingLogSink, Graph, LogNode, NodeBehavior, NodeBehaviors, OnStartNode, Runtime, Severity, VariableDeclaration, VariableGetNode};
+
+    let floats: [&[f32]; 4] = [
+        &[0.0, 1.0],
+        &[0.0, 0.0, 0.0, 2.0, 4.0, 6.0],
+        &[0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 0.0],
+        // in-tangent, value, out-tangent per keyframe
+        &[0.0, 0.0, 0.0, 0.0, 1.0, 0.0],
+    ];
+    let bin = floats.iter().flat_map(|a| a.iter()).flat_map(|a| a.to_le_bytes()).collect::<Vec<_>>();
+    let json = format!(r#"{{
+        "nodes": [{{ "mesh": 0 }}],
+        "meshes": [{{ "weights": [0] }}],
+        "buffers": [{{ "byteLength": {} }}],
+        "bufferViews": [{{ "buffer": 0, "byteLength": {} }}],
+        "accessors": [
+            {{ "bufferView": 0, "componentType": 5126, "count": 2, "type": "SCALAR" }},
+            {{ "bufferView": 0, "byteOffset": 8, "componentType": 5126, "count": 2, "type": "VEC3" }},
+            {{ "bufferView": 0, "byteOffset": 32, "componentType": 5126, "count": 2, "type": "VEC4" }},
+            {{ "bufferView": 0, "byteOffset": 64, "componentType": 5126, "count": 6, "type": "SCALAR" }}
+        ],
+        "animations": [{{
+            "channels": [
+                {{ "sampler": 0, "target": {{ "node": 0, "path": "translation" }} }},
+                {{ "sampler": 1, "target": {{ "node": 0, "path": "rotation" }} }},
+                {{ "sampler": 2, "target": {{ "node": 0, "path": "weights" }} }}
+            ],
+            "samplers": [
+                {{ "input": 0, "output": 1 }},
+                {{ "input": 0, "output": 2, "interpolation": "STEP" }},
+                {{ "input": 0, "output": 3, "interpolation": "CUBICSPLINE" }}
+            ]
+        }}]
+    }}"#, bin.len(), bin.len());
+    let mut glb = vec![];
+    let json = json.into_bytes();
+    let padding = (4 - json.len() % 4) % 4;
+    for word in [0x4654_6C67u32, 2, (12 + 8 + json.len() + padding + 8 + bin.len()) as u32, (json.len() + padding) as u32, 0x4E4F_534A] {
+        glb.extend(word.to_le_bytes());
+    }
+    glb.extend(json);
+    glb.extend(std::iter::repeat_n(b' ', padding));
+    glb.extend((bin.len() as u32).to_le_bytes());
+    glb.extend(0x004E_4942u32.to_le_bytes());
+    glb.extend(&bin);
+    let world = GltfWorld::from_glb(&glb).unwrap();
+    assert_eq!(world.animations[0].duration, 1.0);
+
+    // Keyframes at the same time would divide by zero when sampled.
+    let mut repeated = glb.clone();
+    let first = repeated.len() - bin.len();
+    repeated[first..first + 4].copy_from_slice(&1.0f32.to_le_bytes());
+    assert!(matches!(GltfWorld::from_glb(&repeated), Err(GltfError::Animation(0))));
+
+    let mut graph = Graph::new();
+    let mut node_behaviors = NodeBehaviors(HashMap::new());
+
+    let start_id = 0;
+    let animation_start_id = 1;
+    let log_id = 2;
+    let constant_ids = [3, 4, 5, 6];
+
+    let start = OnStartNode::new(start_id);
+    let animation_start = AnimationStartNode::new(animation_start_id);
+    let log = LogNode::new(log_id, "done", Severity::Info);
+
+    graph
+        .add_node(start.node())
+        .add_node(animation_start.node())
+        .add_node(log.node())
+        .link_flow(start_id, "out", animation_start_id, "in")
+        .link_flow(animation_start_id, "done", log_id, "in");
+    node_behaviors.add(Box::new(start));
+    node_behaviors.add(Box::new(animation_start));
+    node_behaviors.add(Box::new(log));
+    for (i, (node_id, socket)) in constant_ids.into_iter().zip(["animation", "startTime", "endTime", "speed"]).enumerate() {
+        let constant = VariableGetNode::new(node_id, i);
+        graph
+            .add_node(constant.node())
+            .link_value(node_id, "value", animation_start_id, socket);
+        node_behaviors.add(Box::new(constant));
+    }
+
+    let sink = CollectingLogSink::default();
+    let mut runtime = Runtime::with_world(graph.resolve().unwrap(), node_behaviors, world);
+    runtime.set_log_sink(sink.clone());
+    for (name, value) in [("animation", Value::Int(0)), ("startTime", Value::Float(0.0)), ("endTime", Value::Float(1.0)), ("speed", Value::Float(1.0))] {
+        runtime.add_variable(VariableDeclaration {
+            name: name.to_string(),
+            value_type: value.value_type(),
+            value,
+        }).unwrap();
+    }
+
+    runtime.tick(0.5);
+    assert_eq!(runtime.world().read_pointer("/nodes/0/translation"), Some(Value::Float3([1.0, 2.0, 3.0])));
+    assert_eq!(runtime.world().read_pointer("/nodes/0/rotation"), Some(Value::Float4([0.0, 0.0, 0.0, 1.0])));
+    assert_eq!(runtime.world().read_pointer("/nodes/0/weights/0"), Some(Value::Float(0.5)));
+    assert!(sink.messages().is_empty());
+
+    runtime.tick(0.75);
+    assert_eq!(runtime.world().read_pointer("/nodes/0/translation"), Some(Value::Float3([2.0, 4.0, 6.0])));
+    assert_eq!(runtime.world().read_pointer("/nodes/0/rotation"), Some(Value::Float4([0.0, 0.0, 1.0, 0.0])));
+    assert_eq!(runtime.world().read_pointer("/nodes/0/weights/0"), Some(Value::Float(1.0)));
+    assert_eq!(sink.messages(), vec!["done"]);
+    assert!(runtime.world().playing.is_empty());
+}
+
+#[test]
+fn test_gltf_interactivity() {
+    use crate::{CollectingLogSink, Configuration, Declarations, Graph, LoadError, NodeRegistry, Runtime};
+    use crate::registry::PlayNode;
+
+    let mut registry = NodeRegistry::builtin();
+    let played = PlayNode::register_with(&mut registry);
+    let declarations = Declarations::new();
+    assert_eq!(registry.descriptor("ACME_audio/play").unwrap().extension(), Some("ACME_audio"));
+
+    let mut graph = Graph::new();
+    graph
+        .add_node(registry.build("event/onStart", 0, &Configuration::new(), &declarations).unwrap())
+        .add_node(registry.build("ACME_audio/play", 1, &Configuration::new().with("clip", 3), &declarations).unwrap())
+        .add_node(registry.build("math/pi", 2, &Configuration::new(), &declarations).unwrap())
+        .add_node(registry.build("debug/log", 3, &Configuration::new().with("message", "played"), &declarations).unwrap())
+        .link_flow(0, "out", 1, "in")
+        .link_value(2, "value", 1, "volume")
+        .link_flow(1, "out", 3, "in");
+    let mut document = serde_json::json!({ "asset": { "version": "2.0" }, "extensionsUsed": ["KHR_lights_punctual"] });
+    write_graph(&graph, &registry, &mut document).unwrap();
+    assert_eq!(document["extensionsUsed"], serde_json::json!(["KHR_lights_punctual", "KHR_interactivity", "ACME_audio"]));
+    let exported = &document["extensions"]["KHR_interactivity"]["graphs"][0];
+    assert_eq!(exported["types"], serde_json::json!([{ "signature": "float" }]));
+    assert_eq!(exported["declarations"][1], serde_json::json!({
+        "op": "ACME_audio/play",
+        "extension": "ACME_audio",
+        "inputValueSockets": { "volume": { "type": 0 } }
+    }));
+    assert_eq!(exported["declarations"][0], serde_json::json!({ "op": "event/onStart" }));
+    assert_eq!(exported["nodes"][1]["configuration"], serde_json::json!({ "clip": { "value": [3] } }));
+    assert_eq!(exported["nodes"][1]["values"], serde_json::json!({ "volume": { "node": 2, "socket": "value" } }));
+
+    // Reading it back resolves the vendor op through the registry.
+    let json = document.to_string();
+    let read = read_graph(&json, &registry).unwrap();
+    assert_eq!(read.nodes, graph.nodes);
+    let sink = CollectingLogSink::default();
+    let mut runtime = Runtime::load(&registry, &read).unwrap();
+    runtime.set_log_sink(sink.clone());
+    runtime.start();
+    assert_eq!(*played.lock().unwrap(), vec![(3, Value::Float(std::f32::consts::PI))]);
+    assert_eq!(sink.messages(), vec!["played"]);
+
+    assert!(matches!(
+        read_graph(&json, &NodeRegistry::builtin()),
+        Err(InteractivityError::Load(LoadError::UnknownOp { node_id: 1, .. }))
+    ));
+    let mut document = serde_json::from_str::<serde_json::Value>(&json).unwrap();
+    document["extensions"]["KHR_interactivity"]["graphs"][0]["types"][0]["signature"] = "int".into();
+    assert!(matches!(
+        read_graph(&document.to_string(), &registry),
+        Err(InteractivityError::SignatureMismatch { socket, .. }) if socket == "volume"
+    ));
+    document["extensions"]["KHR_interactivity"]["graphs"][0]["types"][0]["signature"] = "float".into();
+    document["extensions"]["KHR_interactivity"]["graphs"][0]["nodes"][1]["values"]["volume"] = serde_json::json!({ "value": [true], "type": 0 });
+    assert!(matches!(
+        read_graph(&document.to_string(), &registry),
+        Err(InteractivityError::InvalidValue { node_id: 1, .. })
+    ));
+}
+
+#[test]
+fn test_gltf_interactivity_declarations() {
+    use crate::{Configuration, EventDeclaration, EventValue, Graph, NodeRegistry, Runtime, ValueType, VariableDeclaration};
+
+    // On start, `ping` is sent with an inline value, and its receiver stores
+    // the value in `total`.
+    let registry = NodeRegistry::builtin();
+    let mut graph = Graph::new();
+    graph.declarations.variables.push(VariableDeclaration {
+        name: "total".to_string(),
+        value_type: ValueType::Float,
+        value: Value::Float(0.0),
+    });
+    graph.declarations.events.push(EventDeclaration {
+        id: "ping".to_string(),
+        values: vec![EventValue { name: "amount".to_string(), value_type: ValueType::Float }],
+    });
+    let event = Configuration::new().with("event", 0);
+    let start = registry.build("event/onStart", 0, &Configuration::new(), &graph.declarations).unwrap();
+    let send = registry.build("event/send", 1, &event, &graph.declarations).unwrap();
+    let receive = registry.build("event/receive", 2, &event, &graph.declarations).unwrap();
+    let set = registry.build("variable/set", 3, &Configuration::new().with("variable", 0), &graph.declarations).unwrap();
+    graph
+        .add_node(start)
+        .add_node(send.with_value("amount", Value::Float(2.5)))
+        .add_node(receive)
+        .add_node(set)
+        .link_flow(0, "out", 1, "in")
+        .link_flow(2, "out", 3, "in")
+        .link_value(2, "amount", 3, "value");
+
+    // Writing merges into the extension already in the document, replacing its graph.
+    let mut document = serde_json::json!({
+        "asset": { "version": "2.0" },
+        "extensions": { "KHR_interactivity": { "graphs": [{ "nodes": [] }], "graph": 0, "extras": { "author": "acme" } } }
+    });
+    write_graph(&graph, &registry, &mut document).unwrap();
+    let extension = &document["extensions"]["KHR_interactivity"];
+    assert_eq!(extension["extras"], serde_json::json!({ "author": "acme" }));
+    assert_eq!(extension["graphs"].as_array().unwrap().len(), 1);
+    let exported = &extension["graphs"][0];
+    assert_eq!(exported["types"], serde_json::json!([{ "signature": "float" }]));
+    assert_eq!(exported["variables"], serde_json::json!([{ "id": "total", "type": 0, "value": [0.0] }]));
+    assert_eq!(exported["events"], serde_json::json!([{ "id": "ping", "values": { "amount": { "type": 0 } } }]));
+    assert_eq!(exported["nodes"][1]["values"], serde_json::json!({ "amount": { "value": [2.5], "type": 0 } }));
+
+    let read = read_graph(&document.to_string(), &registry).unwrap();
+    assert_eq!(read, graph);
+    let mut runtime = Runtime::load(&registry, &read).unwrap();
+    runtime.start();
+    runtime.tick(0.1);
+    assert_eq!(runtime.variable(0), Some(&Value::Float(2.5)));
+
+    let mut mistyped = graph;
+    mistyped.nodes[1].values.insert("amount".to_string(), Value::Int(2));
+    assert!(matches!(registry.validate(&mistyped), Err(crate::LoadError::TypeMismatch { node_id: 1, .. })));
+    let mut broken = document;
+    broken["extensions"]["KHR_interactivity"]["graphs"][0]["variables"][0]["value"] = serde_json::json!([0.0, 1.0]);
+    assert!(matches!(read_graph(&broken.to_string(), &registry), Err(InteractivityError::InvalidVariable(0))));
+}
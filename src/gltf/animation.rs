@@ -0,0 +1,245 @@
+use serde::Deserialize;
+use crate::interpolate::slerp;
+use super::buffer::BufferData;
+use super::{GltfError, GltfNode};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+pub enum SamplerInterpolation {
+    #[serde(rename = "STEP")]
+    Step,
+    #[default]
+    #[serde(rename = "LINEAR")]
+    Linear,
+    #[serde(rename = "CUBICSPLINE")]
+    CubicSpline,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChannelPath {
+    Translation,
+    Rotation,
+    Scale,
+    Weights,
+}
+
+/// Keyframe times, strictly increasing, and the values at them. For cubic
+/// splines every keyframe has an in-tangent, a value and an out-tangent, in
+/// that order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sampler {
+    pub input: Vec<f32>,
+    pub output: Vec<f32>,
+    pub interpolation: SamplerInterpolation,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Channel {
+    pub sampler: usize,
+    pub node: usize,
+    pub path: ChannelPath,
+    /// The number of floats the channel writes, e.g. 4 for rotations.
+    pub components: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Animation {
+    pub channels: Vec<Channel>,
+    pub samplers: Vec<Sampler>,
+    /// The time of the last keyframe of any sampler.
+    pub duration: f32,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub(super) struct AnimationData {
+    pub animations: Vec<RawAnimation>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub(super) struct RawAnimation {
+    pub channels: Vec<RawChannel>,
+    pub samplers: Vec<RawSampler>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub(super) struct RawChannel {
+    pub sampler: usize,
+    pub target: RawTarget,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub(super) struct RawTarget {
+    pub node: Option<usize>,
+    pub path: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub(super) struct RawSampler {
+    pub input: usize,
+    pub output: usize,
+    pub interpolation: SamplerInterpolation,
+}
+
+impl Animation {
+    /// Reads the samplers' accessors. Channels targeting anything but a node's
+    /// TRS or morph weights are left out.
+    pub(super) fn load(index: usize, raw: &RawAnimation, data: &BufferData, buffers: &[Vec<u8>]) -> Result<Self, GltfError> {
+        let invalid = || GltfError::Animation(index);
+        let samplers = raw.samplers.iter().map(|sampler| {
+            let input = data.read_accessor(sampler.input, buffers)?;
+            let output = data.read_accessor(sampler.output, buffers)?;
+            Ok(Sampler {
+                input,
+                output,
+                interpolation: sampler.interpolation,
+            })
+        }).collect::<Result<Vec<_>, GltfError>>()?;
+        let mut channels = vec![];
+        for channel in &raw.channels {
+            let Some(node) = channel.target.node else {
+                continue;
+            };
+            let sampler = samplers.get(channel.sampler).ok_or_else(invalid)?;
+            let values_per_key = match sampler.interpolation {
+                SamplerInterpolation::CubicSpline => 3,
+                _ => 1,
+            };
+            let values = sampler.input.len() * values_per_key;
+            let (path, components) = match channel.target.path.as_str() {
+                "translation" => (ChannelPath::Translation, 3),
+                "rotation" => (ChannelPath::Rotation, 4),
+                "scale" => (ChannelPath::Scale, 3),
+                "weights" if values > 0 => (ChannelPath::Weights, sampler.output.len() / values),
+                _ => continue,
+            };
+            let increasing = sampler.input.windows(2).all(|a| a[0] < a[1]);
+            if sampler.input.is_empty() || !increasing || sampler.output.len() != values * components {
+                return Err(invalid());
+            }
+            channels.push(Channel {
+                sampler: channel.sampler,
+                node,
+                path,
+                components,
+            });
+        }
+        let duration = samplers.iter().filter_map(|a| a.input.last()).fold(0.0, |a: f32, b| a.max(*b));
+        Ok(Self {
+            channels,
+            samplers,
+            duration,
+        })
+    }
+
+    /// Poses `nodes` at `time`, in seconds from the start of the animation.
+    pub fn apply(&self, time: f32, nodes: &mut [GltfNode]) {
+        for channel in &self.channels {
+            let Some(node) = nodes.get_mut(channel.node) else {
+                continue;
+            };
+            let value = self.samplers[channel.sampler].sample(time, channel.components, channel.path == ChannelPath::Rotation);
+            match channel.path {
+                ChannelPath::Translation => node.translation.copy_from_slice(&value),
+                ChannelPath::Rotation => node.rotation.copy_from_slice(&value),
+                ChannelPath::Scale => node.scale.copy_from_slice(&value),
+                ChannelPath::Weights => node.weights = Some(value),
+            }
+        }
+    }
+}
+
+impl Sampler {
+    /// The value of the `n`th keyframe, skipping the tangents of cubic splines.
+    fn key(&self, n: usize, components: usize) -> &[f32] {
+        let start = match self.interpolation {
+            SamplerInterpolation::CubicSpline => (n * 3 + 1) * components,
+            _ => n * components,
+        };
+        &self.output[start..start + components]
+    }
+
+    /// The value at `time`, clamped to the first and last keyframes.
+    pub fn sample(&self, time: f32, components: usize, rotation: bool) -> Vec<f32> {
+        let last = self.input.len() - 1;
+        if time <= self.input[0] {
+            return self.key(0, components).to_vec();
+        }
+        if time >= self.input[last] {
+            return self.key(last, components).to_vec();
+        }
+        let next = self.input.partition_point(|a| *a <= time);
+        let previous = next - 1;
+        let delta = self.input[next] - self.input[previous];
+        let t = (time - self.input[previous]) / delta;
+        let (a, b) = (self.key(previous, components), self.key(next, components));
+        match self.interpolation {
+            SamplerInterpolation::Step => a.to_vec(),
+            SamplerInterpolation::Linear if rotation => slerp(a.try_into().unwrap(), b.try_into().unwrap(), t).to_vec(),
+            SamplerInterpolation::Linear => a.iter().zip(b).map(|(a, b)| a + (b - a) * t).collect(),
+            SamplerInterpolation::CubicSpline => {
+                let out_tangent = &self.output[(previous * 3 + 2) * components..][..components];
+                let in_tangent = &self.output[next * 3 * components..][..components];
+                let (t2, t3) = (t * t, t * t * t);
+                let value = (0..components).map(|i| {
+                    (2.0 * t3 - 3.0 * t2 + 1.0) * a[i]
+                        + (t3 - 2.0 * t2 + t) * delta * out_tangent[i]
+                        + (-2.0 * t3 + 3.0 * t2) * b[i]
+                        + (t3 - t2) * delta * in_tangent[i]
+                }).collect::<Vec<_>>();
+                if rotation {
+                    let length = value.iter().map(|a| a * a).sum::<f32>().sqrt();
+                    value.iter().map(|a| a / length).collect()
+                } else {
+                    value
+                }
+            }
+        }
+    }
+}
+
+/// An animation being played by the world.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Playback {
+    pub animation: usize,
+    pub time: f32,
+    pub end_time: f32,
+    pub speed: f32,
+    /// Set by `animation/stopAt`, replacing `end_time`.
+    pub stop_time: Option<f32>,
+}
+
+impl Playback {
+    fn direction(&self) -> f32 {
+        if self.end_time < self.time { -1.0 } else { 1.0 }
+    }
+
+    /// Moves the playback `dt` seconds on and returns whether it reached its
+    /// end or stop time.
+    pub fn advance(&mut self, dt: f32) -> bool {
+        let direction = self.direction();
+        let target = self.stop_time.unwrap_or(self.end_time);
+        self.time += dt * self.speed * direction;
+        let finished = match direction > 0.0 {
+            true => self.time >= target,
+            false => self.time <= target,
+        };
+        if finished {
+            self.time = target;
+        }
+        finished
+    }
+
+    /// The time within the animation, which wraps around outside of it so that
+    /// unbounded playback loops.
+    pub fn animation_time(&self, duration: f32) -> f32 {
+        if duration <= 0.0 || (0.0..=duration).contains(&self.time) {
+            self.time
+        } else {
+            self.time.rem_euclid(duration)
+        }
+    }
+}
@@ -0,0 +1,218 @@
+use serde::Deserialize;
+use super::GltfError;
+
+/// The parts of a glTF document needed to read accessor data.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub(super) struct BufferData {
+    pub accessors: Vec<Accessor>,
+    pub buffer_views: Vec<BufferView>,
+    pub buffers: Vec<Buffer>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub(super) struct Accessor {
+    pub buffer_view: Option<usize>,
+    pub byte_offset: usize,
+    pub component_type: u32,
+    pub normalized: bool,
+    pub count: usize,
+    #[serde(rename = "type")]
+    pub accessor_type: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub(super) struct BufferView {
+    pub buffer: usize,
+    pub byte_offset: usize,
+    pub byte_length: usize,
+    pub byte_stride: Option<usize>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub(super) struct Buffer {
+    pub uri: Option<String>,
+}
+
+/// The most values an accessor without a buffer view is read as, since its
+/// zeros take memory that the document does not.
+const MAX_ZEROED_VALUES: usize = 1 << 24;
+
+const GLB_MAGIC: u32 = 0x4654_6C67;
+const GLB_JSON: u32 = 0x4E4F_534A;
+const GLB_BIN: u32 = 0x004E_4942;
+
+fn read_u32(bytes: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(bytes.get(offset..offset + 4)?.try_into().ok()?))
+}
+
+/// Splits a binary glTF into its JSON chunk and its optional binary chunk.
+pub(super) fn split_glb(bytes: &[u8]) -> Result<(&str, Option<&[u8]>), GltfError> {
+    let invalid = |reason: &str| GltfError::Glb(reason.to_string());
+    if read_u32(bytes, 0) != Some(GLB_MAGIC) {
+        return Err(invalid("missing glTF magic"));
+    }
+    if read_u32(bytes, 4) != Some(2) {
+        return Err(invalid("unsupported version"));
+    }
+    let mut json = None;
+    let mut bin = None;
+    let mut offset = 12;
+    while offset < bytes.len() {
+        let length = read_u32(bytes, offset).ok_or_else(|| invalid("truncated chunk header"))? as usize;
+        let chunk_type = read_u32(bytes, offset + 4).ok_or_else(|| invalid("truncated chunk header"))?;
+        let data = bytes.get(offset + 8..offset + 8 + length).ok_or_else(|| invalid("truncated chunk"))?;
+        match chunk_type {
+            GLB_JSON if json.is_none() => json = Some(std::str::from_utf8(data).map_err(|_| invalid("JSON chunk is not UTF-8"))?),
+            GLB_BIN if bin.is_none() => bin = Some(data),
+            _ => {}
+        }
+        offset += 8 + length;
+    }
+    Ok((json.ok_or_else(|| invalid("missing JSON chunk"))?, bin))
+}
+
+fn decode_base64(data: &str) -> Option<Vec<u8>> {
+    let digit = |c: u8| match c {
+        b'A'..=b'Z' => Some(c - b'A'),
+        b'a'..=b'z' => Some(c - b'a' + 26),
+        b'0'..=b'9' => Some(c - b'0' + 52),
+        b'+' => Some(62),
+        b'/' => Some(63),
+        _ => None,
+    };
+    let mut bytes = vec![];
+    let mut bits = 0u32;
+    let mut count = 0;
+    for c in data.bytes().filter(|c| *c != b'=') {
+        bits = bits << 6 | digit(c)? as u32;
+        count += 6;
+        if count >= 8 {
+            count -= 8;
+            bytes.push((bits >> count) as u8);
+        }
+    }
+    Some(bytes)
+}
+
+impl BufferData {
+    /// The contents of every buffer: embedded base64 data URIs, or the binary
+    /// chunk for a buffer without a URI. External files are not supported.
+    pub fn load_buffers(&self, bin: Option<&[u8]>) -> Result<Vec<Vec<u8>>, GltfError> {
+        self.buffers.iter().enumerate().map(|(i, buffer)| {
+            let data = match &buffer.uri {
+                Some(uri) => uri.strip_prefix("data:")
+                    .and_then(|a| a.split_once(";base64,"))
+                    .and_then(|(_, data)| decode_base64(data)),
+                None if i == 0 => bin.map(|a| a.to_vec()),
+                None => None,
+            };
+            data.ok_or(GltfError::Buffer(i))
+        }).collect()
+    }
+
+    /// The elements of an accessor as floats, components one after another.
+    /// Normalized integers are mapped to `[0, 1]` or `[-1, 1]`.
+    pub fn read_accessor(&self, index: usize, buffers: &[Vec<u8>]) -> Result<Vec<f32>, GltfError> {
+        let invalid = || GltfError::Accessor(index);
+        let accessor = self.accessors.get(index).ok_or_else(invalid)?;
+        let components = match accessor.accessor_type.as_str() {
+            "SCALAR" => 1,
+            "VEC2" => 2,
+            "VEC3" => 3,
+            "VEC4" => 4,
+            "MAT4" => 16,
+            _ => return Err(invalid()),
+        };
+        let size = match accessor.component_type {
+            5120 | 5121 => 1,
+            5122 | 5123 => 2,
+            5126 => 4,
+            _ => return Err(invalid()),
+        };
+        let Some(view) = accessor.buffer_view else {
+            let values = accessor.count.checked_mul(components).filter(|a| *a <= MAX_ZEROED_VALUES).ok_or_else(invalid)?;
+            return Ok(vec![0.0; values]);
+        };
+        let view = self.buffer_views.get(view).ok_or_else(invalid)?;
+        let data = view.byte_offset.checked_add(view.byte_length)
+            .and_then(|end| buffers.get(view.buffer)?.get(view.byte_offset..end))
+            .ok_or_else(invalid)?;
+        let stride = view.byte_stride.unwrap_or(size * components);
+        // Every element has to be in the view before anything is allocated for
+        // them, which also keeps the offsets below from overflowing.
+        if let Some(last) = accessor.count.checked_sub(1) {
+            let end = last.checked_mul(stride)
+                .and_then(|a| a.checked_add(accessor.byte_offset))
+                .and_then(|a| a.checked_add(size * components));
+            if end.is_none_or(|a| a > data.len()) {
+                return Err(invalid());
+            }
+        }
+        let mut values = Vec::with_capacity(accessor.count * components);
+        for element in 0..accessor.count {
+            for component in 0..components {
+                let offset = accessor.byte_offset + element * stride + component * size;
+                let bytes = data.get(offset..offset + size).ok_or_else(invalid)?;
+                let value = match (accessor.component_type, accessor.normalized) {
+                    (5126, _) => f32::from_le_bytes(bytes.try_into().unwrap()),
+                    (5120, true) => (bytes[0] as i8 as f32 / 127.0).max(-1.0),
+                    (5120, false) => bytes[0] as i8 as f32,
+                    (5121, true) => bytes[0] as f32 / 255.0,
+                    (5121, false) => bytes[0] as f32,
+                    (5122, normalized) => {
+                        let value = i16::from_le_bytes(bytes.try_into().unwrap()) as f32;
+                        if normalized { (value / 32767.0).max(-1.0) } else { value }
+                    }
+                    (_, normalized) => {
+                        let value = u16::from_le_bytes(bytes.try_into().unwrap()) as f32;
+                        if normalized { value / 65535.0 } else { value }
+                    }
+                };
+                values.push(value);
+            }
+        }
+        Ok(values)
+    }
+}
+
+#[test]
+fn test_decode_base64() {
+    assert_eq!(decode_base64("AACAPw=="), Some(1.0f32.to_le_bytes().to_vec()));
+    assert_eq!(decode_base64("TWFu"), Some(b"Man".to_vec()));
+    assert_eq!(decode_base64("TW*u"), None);
+}
+
+#[test]
+fn test_read_accessor() {
+    let accessor = |buffer_view, count| Accessor {
+        buffer_view,
+        component_type: 5126,
+        count,
+        accessor_type: "VEC2".to_string(),
+        ..Default::default()
+    };
+    let mut data = BufferData {
+        accessors: vec![accessor(Some(0), 2)],
+        buffer_views: vec![BufferView { byte_length: 16, ..Default::default() }],
+        buffers: vec![],
+    };
+    let buffers = vec![[1.0f32, 2.0, 3.0, 4.0].iter().flat_map(|a| a.to_le_bytes()).collect::<Vec<_>>()];
+    assert_eq!(data.read_accessor(0, &buffers).unwrap(), vec![1.0, 2.0, 3.0, 4.0]);
+
+    // Counts the data cannot hold are rejected before anything is allocated.
+    for count in [3, 1_000_000_000_000_000, usize::MAX] {
+        data.accessors[0].count = count;
+        assert!(matches!(data.read_accessor(0, &buffers), Err(GltfError::Accessor(0))));
+    }
+    data.accessors[0] = accessor(None, 1_000_000_000_000_000);
+    assert!(matches!(data.read_accessor(0, &buffers), Err(GltfError::Accessor(0))));
+    data.accessors[0].count = 3;
+    assert_eq!(data.read_accessor(0, &buffers).unwrap(), vec![0.0; 6]);
+    data.accessors[0] = accessor(Some(0), 1);
+    data.buffer_views[0].byte_offset = usize::MAX;
+    assert!(matches!(data.read_accessor(0, &buffers), Err(GltfError::Accessor(0))));
+}
@@ -0,0 +1,459 @@
+use std::collections::{BTreeMap, HashMap};
+use serde::{Deserialize, Serialize};
+use crate::{Configuration, ConfigurationError, ConfigurationType, ConfigurationValue, EventDeclaration, EventValue, Graph, GraphError, LoadError, NodeId, NodeRegistry, Value, ValueSocketDescriptor, ValueType, VariableDeclaration};
+
+const EXTENSION: &str = "KHR_interactivity";
+
+#[derive(Debug, thiserror::Error)]
+pub enum InteractivityError {
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    #[error("the glTF document is invalid: {0}")]
+    InvalidDocument(&'static str),
+    #[error("the document has no KHR_interactivity graph")]
+    MissingGraph,
+    #[error("node {node_id} uses declaration {declaration}, which is not in the graph")]
+    UnknownDeclaration { node_id: NodeId, declaration: usize },
+    #[error("type {0} is not in the graph or has an unsupported signature")]
+    UnknownType(usize),
+    #[error("input value socket `{socket}` of node {node_id} holds a value that does not match its type")]
+    InvalidValue { node_id: NodeId, socket: String },
+    #[error("variable {0} holds a value that does not match its type")]
+    InvalidVariable(usize),
+    #[error("`{op}` is declared with a socket `{socket}` its registered type does not have, or not with that type")]
+    SignatureMismatch { op: String, socket: String },
+    #[error(transparent)]
+    Load(#[from] LoadError),
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct Document {
+    extensions: DocumentExtensions,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct DocumentExtensions {
+    #[serde(rename = "KHR_interactivity")]
+    khr_interactivity: Option<Extension>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct Extension {
+    graphs: Vec<RawGraph>,
+    graph: usize,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct RawGraph {
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    types: Vec<RawType>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    variables: Vec<RawVariable>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    events: Vec<RawEvent>,
+    declarations: Vec<RawDeclaration>,
+    nodes: Vec<RawNode>,
+}
+
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+struct RawType {
+    signature: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct RawVariable {
+    id: String,
+    #[serde(rename = "type")]
+    value_type: usize,
+    value: Vec<serde_json::Value>,
+}
+
+/// A custom event. Its values are keyed by name, so they are read back in
+/// name order.
+#[derive(Debug, Serialize, Deserialize)]
+struct RawEvent {
+    id: String,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    values: BTreeMap<String, RawSignature>,
+}
+
+/// An op the nodes of the graph use. Ops of extensions carry the signature
+/// of their value sockets, since a reader may not know them.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct RawDeclaration {
+    op: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    extension: Option<String>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    input_value_sockets: BTreeMap<String, RawSignature>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    output_value_sockets: BTreeMap<String, RawSignature>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct RawSignature {
+    /// An index into the types of the graph.
+    #[serde(rename = "type")]
+    value_type: usize,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct RawNode {
+    declaration: usize,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    configuration: BTreeMap<String, RawConfiguration>,
+    /// By input value socket.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    values: BTreeMap<String, RawValue>,
+    /// By output flow socket.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    flows: BTreeMap<String, RawSocket>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct RawConfiguration {
+    value: Vec<serde_json::Value>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+enum RawValue {
+    Link(RawSocket),
+    Inline {
+        value: Vec<serde_json::Value>,
+        #[serde(rename = "type")]
+        value_type: usize,
+    },
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct RawSocket {
+    node: usize,
+    socket: String,
+}
+
+fn signature(value_type: ValueType) -> &'static str {
+    match value_type {
+        ValueType::Float => "float",
+        ValueType::Bool => "bool",
+        ValueType::Int => "int",
+        ValueType::Float2 => "float2",
+        ValueType::Float3 => "float3",
+        ValueType::Float4 => "float4",
+        ValueType::Float4x4 => "float4x4",
+    }
+}
+
+fn value_type(signature: &str) -> Option<ValueType> {
+    Some(match signature {
+        "float" => ValueType::Float,
+        "bool" => ValueType::Bool,
+        "int" => ValueType::Int,
+        "float2" => ValueType::Float2,
+        "float3" => ValueType::Float3,
+        "float4" => ValueType::Float4,
+        "float4x4" => ValueType::Float4x4,
+        _ => return None,
+    })
+}
+
+/// The index of `value_type` in `types`, adding it if it is not there yet.
+fn type_index(value_type: ValueType, types: &mut Vec<RawType>) -> usize {
+    let raw = RawType {
+        signature: signature(value_type).to_string(),
+    };
+    types.iter().position(|a| *a == raw).unwrap_or_else(|| {
+        types.push(raw);
+        types.len() - 1
+    })
+}
+
+fn read_type(index: usize, types: &[RawType]) -> Result<ValueType, InteractivityError> {
+    types.get(index)
+        .and_then(|a| value_type(&a.signature))
+        .ok_or(InteractivityError::UnknownType(index))
+}
+
+/// Values are arrays of their components in the document, like configuration.
+fn write_value(value: &Value) -> Vec<serde_json::Value> {
+    let floats = |a: &[f32]| a.iter().map(|b| (*b).into()).collect();
+    match value {
+        Value::Float(a) => vec![(*a).into()],
+        Value::Bool(a) => vec![(*a).into()],
+        Value::Int(a) => vec![(*a).into()],
+        Value::Float2(a) => floats(a),
+        Value::Float3(a) => floats(a),
+        Value::Float4(a) => floats(a),
+        Value::Float4x4(a) => floats(a),
+    }
+}
+
+fn read_value(value_type: ValueType, raw: &[serde_json::Value]) -> Option<Value> {
+    let floats = || raw.iter().map(|a| a.as_f64().map(|b| b as f32)).collect::<Option<Vec<_>>>();
+    Some(match (value_type, raw) {
+        (ValueType::Float, [a]) => Value::Float(a.as_f64()? as f32),
+        (ValueType::Bool, [a]) => Value::Bool(a.as_bool()?),
+        (ValueType::Int, [a]) => Value::Int(i32::try_from(a.as_i64()?).ok()?),
+        (ValueType::Float2, _) => Value::Float2(floats()?.try_into().ok()?),
+        (ValueType::Float3, _) => Value::Float3(floats()?.try_into().ok()?),
+        (ValueType::Float4, _) => Value::Float4(floats()?.try_into().ok()?),
+        (ValueType::Float4x4, _) => Value::Float4x4(floats()?.try_into().ok()?),
+        _ => return None,
+    })
+}
+
+/// Configuration values are arrays in the document, even single ones.
+fn write_configuration(value: &ConfigurationValue) -> RawConfiguration {
+    let value = match value {
+        ConfigurationValue::Bool(a) => vec![(*a).into()],
+        ConfigurationValue::Int(a) => vec![(*a).into()],
+        ConfigurationValue::IntArray(a) => a.iter().map(|b| (*b).into()).collect(),
+        ConfigurationValue::String(a) => vec![a.clone().into()],
+        ConfigurationValue::StringArray(a) => a.iter().map(|b| b.clone().into()).collect(),
+    };
+    RawConfiguration {
+        value,
+    }
+}
+
+fn read_configuration(name: &str, configuration_type: ConfigurationType, raw: &RawConfiguration) -> Result<ConfigurationValue, ConfigurationError> {
+    let int = |a: &serde_json::Value| a.as_i64().and_then(|b| i32::try_from(b).ok());
+    let value = match (configuration_type, raw.value.as_slice()) {
+        (ConfigurationType::Bool, [serde_json::Value::Bool(a)]) => Some(ConfigurationValue::Bool(*a)),
+        (ConfigurationType::Int, [a]) => int(a).map(ConfigurationValue::Int),
+        (ConfigurationType::IntArray, values) => values.iter().map(int).collect::<Option<_>>().map(ConfigurationValue::IntArray),
+        (ConfigurationType::String, [serde_json::Value::String(a)]) => Some(ConfigurationValue::String(a.clone())),
+        (ConfigurationType::StringArray, values) => values.iter().map(|a| a.as_str().map(str::to_string)).collect::<Option<_>>().map(ConfigurationValue::StringArray),
+        _ => None,
+    };
+    value.ok_or_else(|| ConfigurationError::invalid(name, format!("it does not hold a {configuration_type:?} value")))
+}
+
+/// The signature of the typed sockets among `sockets`, adding their types to `types`.
+fn write_signatures(sockets: &[ValueSocketDescriptor], types: &mut Vec<RawType>) -> BTreeMap<String, RawSignature> {
+    sockets.iter()
+        .filter_map(|socket| {
+            let value_type = type_index(socket.value_type?, types);
+            Some((socket.name.clone(), RawSignature { value_type }))
+        })
+        .collect()
+}
+
+/// Checks a declared signature against the sockets the registered type lays
+/// out. Sockets whose registered type depends on what they are linked to
+/// accept any declared type.
+fn check_signatures(
+    op: &str,
+    declared: &BTreeMap<String, RawSignature>,
+    types: &[RawType],
+    registered: impl Fn(&str) -> Option<Option<ValueType>>,
+) -> Result<(), InteractivityError> {
+    for (socket, declared) in declared {
+        let declared_type = read_type(declared.value_type, types)?;
+        if !registered(socket).is_some_and(|a| a.is_none_or(|b| b == declared_type)) {
+            return Err(InteractivityError::SignatureMismatch {
+                op: op.to_string(),
+                socket: socket.clone(),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Reads the `KHR_interactivity` graph of a glTF JSON document. Every op,
+/// including those of vendor extensions such as `ACME_audio/play`, is
+/// resolved through `registry`, which lays out the sockets of each node for
+/// its configuration. Nodes are numbered by their index in the document.
+pub fn read_graph(json: &str, registry: &NodeRegistry) -> Result<Graph, InteractivityError> {
+    let extension = serde_json::from_str::<Document>(json)?.extensions.khr_interactivity
+        .ok_or(InteractivityError::MissingGraph)?;
+    let raw = extension.graphs.get(extension.graph).ok_or(InteractivityError::MissingGraph)?;
+
+    let mut graph = Graph::new();
+    for (index, variable) in raw.variables.iter().enumerate() {
+        let value_type = read_type(variable.value_type, &raw.types)?;
+        graph.declarations.variables.push(VariableDeclaration {
+            name: variable.id.clone(),
+            value_type,
+            value: read_value(value_type, &variable.value).ok_or(InteractivityError::InvalidVariable(index))?,
+        });
+    }
+    for event in &raw.events {
+        let values = event.values.iter()
+            .map(|(name, signature)| Ok(EventValue {
+                name: name.clone(),
+                value_type: read_type(signature.value_type, &raw.types)?,
+            }))
+            .collect::<Result<_, InteractivityError>>()?;
+        graph.declarations.events.push(EventDeclaration {
+            id: event.id.clone(),
+            values,
+        });
+    }
+
+    for (index, node) in raw.nodes.iter().enumerate() {
+        let node_id = index as NodeId;
+        let declaration = raw.declarations.get(node.declaration).ok_or(InteractivityError::UnknownDeclaration {
+            node_id,
+            declaration: node.declaration,
+        })?;
+        let descriptor = registry.descriptor(&declaration.op).ok_or_else(|| LoadError::UnknownOp {
+            node_id,
+            op: declaration.op.clone(),
+        })?;
+        let mut configuration = Configuration::new();
+        for (name, value) in &node.configuration {
+            let value = descriptor.configuration.iter()
+                .find(|a| &a.name == name)
+                .ok_or_else(|| ConfigurationError::Unknown(name.clone()))
+                .and_then(|a| read_configuration(name, a.configuration_type, value))
+                .map_err(|source| LoadError::Configuration { node_id, source })?;
+            configuration.set(name, value);
+        }
+
+        let behavior = registry.new_node(&declaration.op, node_id, &configuration, &graph.declarations)?;
+        let layout = behavior.layout();
+        check_signatures(&declaration.op, &declaration.input_value_sockets, &raw.types, |a| Some(layout.input_value_socket(a)?.value_type))?;
+        check_signatures(&declaration.op, &declaration.output_value_sockets, &raw.types, |a| Some(layout.output_value_socket(a)?.value_type))?;
+        let mut new_node = behavior.node();
+
+        for (socket, value) in &node.values {
+            match value {
+                RawValue::Link(output) => {
+                    graph.link_value(output.node as NodeId, &output.socket, node_id, socket);
+                }
+                RawValue::Inline { value, value_type } => {
+                    let value = read_value(read_type(*value_type, &raw.types)?, value).ok_or_else(|| InteractivityError::InvalidValue {
+                        node_id,
+                        socket: socket.clone(),
+                    })?;
+                    new_node.values.insert(socket.clone(), value);
+                }
+            }
+        }
+        graph.add_node(new_node);
+        for (socket, input) in &node.flows {
+            graph.link_flow(node_id, socket, input.node as NodeId, &input.socket);
+        }
+    }
+    Ok(graph)
+}
+
+/// Writes `graph` into a glTF JSON document as its `KHR_interactivity`
+/// graph, once it passes [`NodeRegistry::validate`]. Ops of vendor
+/// extensions are declared with the signature of their value sockets, and
+/// their extensions are added to `extensionsUsed` next to
+/// `KHR_interactivity`. A graph already in the document is replaced, and
+/// anything else the extension holds is kept.
+pub fn write_graph(graph: &Graph, registry: &NodeRegistry, document: &mut serde_json::Value) -> Result<(), InteractivityError> {
+    registry.validate(graph)?;
+    let indices = graph.nodes.iter().enumerate().map(|(i, a)| (a.node_id, i)).collect::<HashMap<_, _>>();
+    let index = |node_id: NodeId| indices.get(&node_id).copied().ok_or(LoadError::Graph(GraphError::UnknownNode(node_id)));
+
+    let mut raw = RawGraph::default();
+    for variable in &graph.declarations.variables {
+        raw.variables.push(RawVariable {
+            id: variable.name.clone(),
+            value_type: type_index(variable.value_type, &mut raw.types),
+            value: write_value(&variable.value),
+        });
+    }
+    for event in &graph.declarations.events {
+        raw.events.push(RawEvent {
+            id: event.id.clone(),
+            values: event.values.iter()
+                .map(|a| (a.name.clone(), RawSignature { value_type: type_index(a.value_type, &mut raw.types) }))
+                .collect(),
+        });
+    }
+    let mut extensions = vec![EXTENSION.to_string()];
+    for node in &graph.nodes {
+        let layout = registry.new_node(&node.op, node.node_id, &node.configuration, &graph.declarations)?.layout();
+        let mut declaration = RawDeclaration {
+            op: node.op.clone(),
+            ..Default::default()
+        };
+        if let Some(extension) = layout.extension() {
+            if !extensions.iter().any(|a| a == extension) {
+                extensions.push(extension.to_string());
+            }
+            declaration.extension = Some(extension.to_string());
+            declaration.input_value_sockets = write_signatures(&layout.input_value_sockets, &mut raw.types);
+            declaration.output_value_sockets = write_signatures(&layout.output_value_sockets, &mut raw.types);
+        }
+        let declaration = raw.declarations.iter().position(|a| *a == declaration).unwrap_or_else(|| {
+            raw.declarations.push(declaration);
+            raw.declarations.len() - 1
+        });
+        let values = node.values.iter()
+            .map(|(socket, value)| (socket.clone(), RawValue::Inline {
+                value: write_value(value),
+                value_type: type_index(value.value_type(), &mut raw.types),
+            }))
+            .collect();
+        raw.nodes.push(RawNode {
+            declaration,
+            configuration: node.configuration.iter().map(|(name, value)| (name.to_string(), write_configuration(value))).collect(),
+            values,
+            ..Default::default()
+        });
+    }
+    for link in &graph.value_links {
+        let output = RawSocket {
+            node: index(link.output.node_id)?,
+            socket: link.output.name.clone(),
+        };
+        raw.nodes[index(link.input.node_id)?].values.insert(link.input.name.clone(), RawValue::Link(output));
+    }
+    for link in &graph.flow_links {
+        let input = RawSocket {
+            node: index(link.input.node_id)?,
+            socket: link.input.name.clone(),
+        };
+        raw.nodes[index(link.output.node_id)?].flows.insert(link.output.name.clone(), input);
+    }
+
+    let root = document.as_object_mut().ok_or(InteractivityError::InvalidDocument("it is not an object"))?;
+    let used = root.entry("extensionsUsed")
+        .or_insert_with(|| serde_json::Value::Array(vec![]))
+        .as_array_mut()
+        .ok_or(InteractivityError::InvalidDocument("`extensionsUsed` is not an array"))?;
+    for extension in extensions {
+        if !used.iter().any(|a| a.as_str() == Some(&extension)) {
+            used.push(extension.into());
+        }
+    }
+    let extension = root.entry("extensions")
+        .or_insert_with(|| serde_json::Value::Object(Default::default()))
+        .as_object_mut()
+        .ok_or(InteractivityError::InvalidDocument("`extensions` is not an object"))?
+        .entry(EXTENSION)
+        .or_insert_with(|| serde_json::Value::Object(Default::default()))
+        .as_object_mut()
+        .ok_or(InteractivityError::InvalidDocument("`KHR_interactivity` is not an object"))?;
+    let current = extension.get("graph").and_then(|a| a.as_u64());
+    let graphs = extension.entry("graphs")
+        .or_insert_with(|| serde_json::Value::Array(vec![]))
+        .as_array_mut()
+        .ok_or(InteractivityError::InvalidDocument("`graphs` is not an array"))?;
+    let raw = serde_json::to_value(raw)?;
+    let graph = match current.map(|a| a as usize).filter(|a| *a < graphs.len()) {
+        Some(graph) => {
+            graphs[graph] = raw;
+            graph
+        }
+        None => {
+            graphs.push(raw);
+            graphs.len() - 1
+        }
+    };
+    extension.insert("graph".to_string(), graph.into());
+    Ok(())
+}
@@ -0,0 +1,334 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use serde::{Deserialize, Serialize};
+use crate::{Configuration, EventDeclaration, NodeId, Value, VariableDeclaration};
+
+/// A node of a [`Graph`]: its type, configuration and the sockets it has.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Node {
+    pub node_id: NodeId,
+    pub op: String,
+    #[serde(default, skip_serializing_if = "Configuration::is_empty")]
+    pub configuration: Configuration,
+    pub input_value_sockets: Vec<String>,
+    pub input_flow_sockets: Vec<String>,
+    pub output_value_sockets: Vec<String>,
+    pub output_flow_sockets: Vec<String>,
+    /// The values of input value sockets that are not linked, by socket.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub values: BTreeMap<String, Value>,
+}
+
+impl Node {
+    /// Gives the input value socket `name` a value instead of a link.
+    pub fn with_value(mut self, name: impl ToString, value: Value) -> Self {
+        self.values.insert(name.to_string(), value);
+        self
+    }
+}
+
+/// A socket of a node, one end of a [`Link`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct SocketRef {
+    pub node_id: NodeId,
+    pub name: String,
+}
+
+/// Connects an output socket to an input socket of the same kind.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Link {
+    pub output: SocketRef,
+    pub input: SocketRef,
+}
+
+impl Link {
+    pub fn new(from: NodeId, output: impl ToString, to: NodeId, input: impl ToString) -> Self {
+        Self {
+            output: SocketRef {
+                node_id: from,
+                name: output.to_string(),
+            },
+            input: SocketRef {
+                node_id: to,
+                name: input.to_string(),
+            },
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, thiserror::Error)]
+pub enum GraphError {
+    #[error("node {0} is in the graph more than once")]
+    DuplicateNode(NodeId),
+    #[error("a link ends at node {0}, which is not in the graph")]
+    UnknownNode(NodeId),
+    #[error("a link ends at `{socket}` of node {node_id}, which has no such socket")]
+    UnknownSocket { node_id: NodeId, socket: String },
+    #[error("input value socket `{socket}` of node {node_id} is not linked")]
+    UnlinkedInput { node_id: NodeId, socket: String },
+    #[error("input value socket `{socket}` of node {node_id} is linked more than once")]
+    DuplicateInput { node_id: NodeId, socket: String },
+    #[error("output flow socket `{socket}` of node {node_id} is linked more than once")]
+    DuplicateFlow { node_id: NodeId, socket: String },
+    #[error("node {node_id} reads a value computed from its own values")]
+    ValueCycle { node_id: NodeId },
+}
+
+/// The variables and custom events of a graph, which nodes refer to by index
+/// in their configuration.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Declarations {
+    pub variables: Vec<VariableDeclaration>,
+    pub events: Vec<EventDeclaration>,
+}
+
+impl Declarations {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.variables.is_empty() && self.events.is_empty()
+    }
+}
+
+/// A graph as it is edited or stored: its nodes and a table of the links
+/// between their sockets, which may not make sense yet. [`Graph::resolve`]
+/// checks it and turns it into what runs.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Graph {
+    #[serde(default, skip_serializing_if = "Declarations::is_empty")]
+    pub declarations: Declarations,
+    pub nodes: Vec<Node>,
+    /// From output value sockets to the input value sockets that read them.
+    pub value_links: Vec<Link>,
+    /// From output flow sockets to the input flow sockets they activate.
+    pub flow_links: Vec<Link>,
+}
+
+impl Graph {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_node(&mut self, node: Node) -> &mut Self {
+        self.nodes.push(node);
+        self
+    }
+
+    /// Lets the input value socket `input` of `to` read the output value socket
+    /// `output` of `from`.
+    pub fn link_value(&mut self, from: NodeId, output: &str, to: NodeId, input: &str) -> &mut Self {
+        self.value_links.push(Link::new(from, output, to, input));
+        self
+    }
+
+    /// Lets the output flow socket `output` of `from` activate the input flow
+    /// socket `input` of `to`.
+    pub fn link_flow(&mut self, from: NodeId, output: &str, to: NodeId, input: &str) -> &mut Self {
+        self.flow_links.push(Link::new(from, output, to, input));
+        self
+    }
+
+    pub fn node(&self, node_id: NodeId) -> Option<&Node> {
+        self.nodes.iter().find(|a| a.node_id == node_id)
+    }
+
+    /// Checks that every link joins sockets the nodes have, that every input
+    /// value socket reads exactly one output or holds a value, that no value
+    /// is computed from itself and that every output flow socket activates at
+    /// most one input, then indexes the links for execution.
+    pub fn resolve(&self) -> Result<ExecutableGraph, GraphError> {
+        let mut nodes = HashMap::new();
+        for node in &self.nodes {
+            if nodes.insert(node.node_id, node.clone()).is_some() {
+                return Err(GraphError::DuplicateNode(node.node_id));
+            }
+        }
+        let check = |socket: &SocketRef, sockets: fn(&Node) -> &Vec<String>| {
+            let node = nodes.get(&socket.node_id).ok_or(GraphError::UnknownNode(socket.node_id))?;
+            if !sockets(node).contains(&socket.name) {
+                return Err(GraphError::UnknownSocket {
+                    node_id: socket.node_id,
+                    socket: socket.name.clone(),
+                });
+            }
+            Ok(())
+        };
+
+        let mut inputs = HashMap::<NodeId, HashMap<String, SocketRef>>::new();
+        for link in &self.value_links {
+            check(&link.output, |a| &a.output_value_sockets)?;
+            check(&link.input, |a| &a.input_value_sockets)?;
+            let previous = inputs.entry(link.input.node_id).or_default()
+                .insert(link.input.name.clone(), link.output.clone());
+            if previous.is_some() {
+                return Err(GraphError::DuplicateInput {
+                    node_id: link.input.node_id,
+                    socket: link.input.name.clone(),
+                });
+            }
+        }
+        for node in &self.nodes {
+            let linked = inputs.get(&node.node_id);
+            for socket in node.values.keys() {
+                check(&SocketRef { node_id: node.node_id, name: socket.clone() }, |a| &a.input_value_sockets)?;
+                if linked.is_some_and(|a| a.contains_key(socket)) {
+                    return Err(GraphError::DuplicateInput {
+                        node_id: node.node_id,
+                        socket: socket.clone(),
+                    });
+                }
+            }
+            if let Some(socket) = node.input_value_sockets.iter().find(|a| linked.is_none_or(|b| !b.contains_key(*a)) && !node.values.contains_key(*a)) {
+                return Err(GraphError::UnlinkedInput {
+                    node_id: node.node_id,
+                    socket: socket.clone(),
+                });
+            }
+        }
+
+        let mut finished = HashSet::new();
+        for node in &self.nodes {
+            check_value_cycles(node.node_id, &inputs, &mut HashSet::new(), &mut finished)?;
+        }
+
+        let mut flows = HashMap::<NodeId, HashMap<String, SocketRef>>::new();
+        for link in &self.flow_links {
+            check(&link.output, |a| &a.output_flow_sockets)?;
+            check(&link.input, |a| &a.input_flow_sockets)?;
+            let previous = flows.entry(link.output.node_id).or_default()
+                .insert(link.output.name.clone(), link.input.clone());
+            if previous.is_some() {
+                return Err(GraphError::DuplicateFlow {
+                    node_id: link.output.node_id,
+                    socket: link.output.name.clone(),
+                });
+            }
+        }
+
+        Ok(ExecutableGraph {
+            nodes,
+            inputs,
+            flows,
+        })
+    }
+}
+
+/// Fails if `node_id` reads, through any number of value links, a value it
+/// computes itself: computing it would request itself without end.
+fn check_value_cycles(
+    node_id: NodeId,
+    inputs: &HashMap<NodeId, HashMap<String, SocketRef>>,
+    visiting: &mut HashSet<NodeId>,
+    finished: &mut HashSet<NodeId>,
+) -> Result<(), GraphError> {
+    if finished.contains(&node_id) {
+        return Ok(());
+    }
+    if !visiting.insert(node_id) {
+        return Err(GraphError::ValueCycle { node_id });
+    }
+    for input in inputs.get(&node_id).into_iter().flat_map(|a| a.values()) {
+        check_value_cycles(input.node_id, inputs, visiting, finished)?;
+    }
+    visiting.remove(&node_id);
+    finished.insert(node_id);
+    Ok(())
+}
+
+/// A [`Graph`] that passed [`Graph::resolve`], with its links indexed by the
+/// socket they are followed from while running.
+#[derive(Debug, Clone, Default)]
+pub struct ExecutableGraph {
+    nodes: HashMap<NodeId, Node>,
+    /// The output value socket each input value socket reads, by node.
+    inputs: HashMap<NodeId, HashMap<String, SocketRef>>,
+    /// The input flow socket each linked output flow socket activates, by node.
+    flows: HashMap<NodeId, HashMap<String, SocketRef>>,
+}
+
+impl ExecutableGraph {
+    pub fn node(&self, node_id: NodeId) -> Option<&Node> {
+        self.nodes.get(&node_id)
+    }
+
+    pub fn nodes(&self) -> impl Iterator<Item = &Node> {
+        self.nodes.values()
+    }
+
+    /// The output value socket the input value socket `name` of `node_id`
+    /// reads, if it is linked rather than given a value.
+    pub fn input(&self, node_id: NodeId, name: &str) -> Option<&SocketRef> {
+        self.inputs.get(&node_id)?.get(name)
+    }
+
+    /// The output value sockets `node_id` reads.
+    pub fn inputs(&self, node_id: NodeId) -> impl Iterator<Item = &SocketRef> {
+        self.inputs.get(&node_id).into_iter().flat_map(|a| a.values())
+    }
+
+    /// The input flow socket the output flow socket `name` of `node_id`
+    /// activates, if it is linked.
+    pub fn flow(&self, node_id: NodeId, name: &str) -> Option<&SocketRef> {
+        self.flows.get(&node_id)?.get(name)
+    }
+}
+
+#[test]
+fn test_graph() {
+    use crate::{MathAdd, MathPi, NodeBehavior, PrintNode, SequenceNode};
+
+    let mut graph = Graph::new();
+    graph
+        .add_node(MathPi::new(0).node())
+        .add_node(MathAdd::new(1).node())
+        .add_node(SequenceNode::new(2, &["out"]).node())
+        .add_node(PrintNode::new(3).node())
+        .link_value(0, "value", 1, "a")
+        .link_value(0, "value", 1, "b")
+        .link_value(1, "value", 3, "print_value")
+        .link_flow(2, "out", 3, "print_input");
+    let executable = graph.resolve().unwrap();
+    assert_eq!(executable.input(1, "b").unwrap(), &SocketRef { node_id: 0, name: "value".to_string() });
+    assert_eq!(executable.inputs(1).count(), 2);
+    assert_eq!(executable.flow(2, "out").unwrap().node_id, 3);
+
+    let mut broken = graph.clone();
+    broken.flow_links.push(Link::new(2, "out", 1, "in"));
+    assert!(matches!(broken.resolve(), Err(GraphError::UnknownSocket { node_id: 1, .. })));
+    let mut broken = graph.clone();
+    broken.link_flow(2, "out", 3, "print_input");
+    assert!(matches!(broken.resolve(), Err(GraphError::DuplicateFlow { node_id: 2, .. })));
+    let mut broken = graph.clone();
+    broken.link_value(0, "value", 1, "a");
+    assert!(matches!(broken.resolve(), Err(GraphError::DuplicateInput { node_id: 1, .. })));
+    let mut broken = graph.clone();
+    broken.value_links.remove(2);
+    assert!(matches!(broken.resolve(), Err(GraphError::UnlinkedInput { node_id: 3, .. })));
+    // A value stands in for a link, but not next to one.
+    let mut inline = graph.clone();
+    inline.value_links.remove(1);
+    inline.nodes[1].values.insert("b".to_string(), Value::Float(1.0));
+    assert!(inline.resolve().unwrap().input(1, "b").is_none());
+    inline.nodes[1].values.insert("a".to_string(), Value::Float(1.0));
+    assert!(matches!(inline.resolve(), Err(GraphError::DuplicateInput { node_id: 1, .. })));
+    inline.nodes[1].values.remove("a");
+    inline.nodes[1].values.insert("c".to_string(), Value::Float(1.0));
+    assert!(matches!(inline.resolve(), Err(GraphError::UnknownSocket { node_id: 1, .. })));
+    // Two additions reading each other would request each other forever.
+    let mut broken = graph.clone();
+    broken.value_links.retain(|a| a.input.node_id != 1 || a.input.name != "b");
+    broken
+        .add_node(MathAdd::new(4).node())
+        .link_value(4, "value", 1, "b")
+        .link_value(1, "value", 4, "a")
+        .link_value(0, "value", 4, "b");
+    assert_eq!(broken.resolve().unwrap_err(), GraphError::ValueCycle { node_id: 1 });
+    let mut broken = graph.clone();
+    broken.link_flow(5, "out", 3, "print_input");
+    assert_eq!(broken.resolve().unwrap_err(), GraphError::UnknownNode(5));
+    let mut broken = graph;
+    broken.add_node(MathPi::new(0).node());
+    assert_eq!(broken.resolve().unwrap_err(), GraphError::DuplicateNode(0));
+}
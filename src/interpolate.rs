@@ -0,0 +1,391 @@
+use serde::{Deserialize, Serialize};
+use crate::pointer::{configured_pointer, with_parameters, PointerError, PointerTemplate};
+use crate::variable::declared_variable;
+use crate::{node, ConfigurationError, ExecutionContext, NodeId, Value, ValueType};
+
+/// What an interpolation writes to. A newer write to the same target cancels it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum InterpolationTarget {
+    /// A resolved pointer.
+    Pointer(String),
+    /// The index of a graph variable.
+    Variable(usize),
+}
+
+/// A value moving from `from` to `to` over `duration` seconds, eased by the
+/// cubic Bézier curve through `(0, 0)`, `p1`, `p2` and `(1, 1)`. The runtime
+/// advances it on every tick and activates `done` of `node_id` once it ends.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Interpolation {
+    pub node_id: NodeId,
+    pub target: InterpolationTarget,
+    pub from: Value,
+    pub to: Value,
+    pub duration: f32,
+    pub elapsed: f32,
+    pub p1: [f32; 2],
+    pub p2: [f32; 2],
+    /// Whether `from` and `to` are rotation quaternions.
+    pub slerp: bool,
+}
+
+impl Interpolation {
+    /// Moves the interpolation `dt` seconds on and returns the value it has
+    /// reached, and whether it is finished.
+    pub fn advance(&mut self, dt: f32) -> (Value, bool) {
+        self.elapsed += dt;
+        let finished = self.elapsed >= self.duration;
+        if finished {
+            return (self.to.clone(), true);
+        }
+        let t = cubic_bezier(self.p1, self.p2, self.elapsed / self.duration);
+        let value = match (&self.from, &self.to) {
+            (Value::Float4(from), Value::Float4(to)) if self.slerp => Value::Float4(slerp(*from, *to, t)),
+            (from, to) => lerp_value(from, to, t).unwrap_or_else(|| to.clone()),
+        };
+        (value, false)
+    }
+}
+
+/// The y of the cubic Bézier easing curve through `(0, 0)`, `p1`, `p2` and
+/// `(1, 1)` at `x`. `p1` and `p2` must have x components in `[0, 1]`, so that
+/// the curve has a single y for every x.
+pub fn cubic_bezier(p1: [f32; 2], p2: [f32; 2], x: f32) -> f32 {
+    let curve = |a: f32, b: f32, s: f32| {
+        let r = 1.0 - s;
+        3.0 * r * r * s * a + 3.0 * r * s * s * b + s * s * s
+    };
+    // x grows monotonically along the curve, so bisection finds its parameter.
+    let (mut low, mut high) = (0.0, 1.0);
+    for _ in 0..32 {
+        let s = (low + high) / 2.0;
+        if curve(p1[0], p2[0], s) < x {
+            low = s;
+        } else {
+            high = s;
+        }
+    }
+    curve(p1[1], p2[1], (low + high) / 2.0)
+}
+
+fn lerp(from: f32, to: f32, t: f32) -> f32 {
+    from + (to - from) * t
+}
+
+fn lerp_array<const N: usize>(from: &[f32; N], to: &[f32; N], t: f32) -> [f32; N] {
+    std::array::from_fn(|i| lerp(from[i], to[i], t))
+}
+
+/// Interpolates two values of the same numeric type componentwise. Ints are
+/// rounded to the nearest value.
+pub fn lerp_value(from: &Value, to: &Value, t: f32) -> Option<Value> {
+    let value = match (from, to) {
+        (Value::Float(from), Value::Float(to)) => Value::Float(lerp(*from, *to, t)),
+        (Value::Int(from), Value::Int(to)) => Value::Int(lerp(*from as f32, *to as f32, t).round() as i32),
+        (Value::Float2(from), Value::Float2(to)) => Value::Float2(lerp_array(from, to, t)),
+        (Value::Float3(from), Value::Float3(to)) => Value::Float3(lerp_array(from, to, t)),
+        (Value::Float4(from), Value::Float4(to)) => Value::Float4(lerp_array(from, to, t)),
+        (Value::Float4x4(from), Value::Float4x4(to)) => Value::Float4x4(lerp_array(from, to, t)),
+        _ => return None,
+    };
+    Some(value)
+}
+
+/// Spherical interpolation of unit quaternions along the shorter arc.
+pub fn slerp(from: [f32; 4], to: [f32; 4], t: f32) -> [f32; 4] {
+    let mut dot = from.iter().zip(&to).map(|(a, b)| a * b).sum::<f32>();
+    let mut to = to;
+    if dot < 0.0 {
+        dot = -dot;
+        to = to.map(|a| -a);
+    }
+    let (a, b) = if dot > 0.9995 {
+        // Nearly parallel, where the sine below loses precision.
+        (1.0 - t, t)
+    } else {
+        let angle = dot.acos();
+        let sin = angle.sin();
+        (((1.0 - t) * angle).sin() / sin, (t * angle).sin() / sin)
+    };
+    let result: [f32; 4] = std::array::from_fn(|i| a * from[i] + b * to[i]);
+    let length = result.iter().map(|a| a * a).sum::<f32>().sqrt();
+    result.map(|a| a / length)
+}
+
+/// Reads the `duration`, `p1` and `p2` inputs shared by the interpolate nodes,
+/// rejecting negative or non-finite durations and control points outside the
+/// unit square's x range.
+fn easing_inputs(ctx: &ExecutionContext, node_id: NodeId) -> Option<(f32, [f32; 2], [f32; 2])> {
+    let duration = ctx.input_float(node_id, "duration")?;
+    let (Value::Float2(p1), Value::Float2(p2)) = (ctx.input_value(node_id, "p1")?, ctx.input_value(node_id, "p2")?) else {
+        return None;
+    };
+    if !duration.is_finite() || duration < 0.0 {
+        return None;
+    }
+    if [p1, p2].iter().flatten().any(|a| !a.is_finite()) || !(0.0..=1.0).contains(&p1[0]) || !(0.0..=1.0).contains(&p2[0]) {
+        return None;
+    }
+    Some((duration, p1, p2))
+}
+
+/// `pointer/interpolate`, moves the property at `pointer` to its `value` input
+/// over `duration` seconds and activates `out`, or `err` if the inputs are
+/// invalid or the property cannot be read or written. `done` activates once
+/// the property reaches the value. Rotations are interpolated with slerp.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PointerInterpolateNode {
+    node_id: NodeId,
+    pointer: PointerTemplate,
+}
+
+impl PointerInterpolateNode {
+    /// Fails if the pointer addresses a read-only property.
+    pub fn new(node_id: NodeId, pointer: PointerTemplate) -> Result<Self, PointerError> {
+        if pointer.read_only() {
+            return Err(PointerError::ReadOnly(pointer.as_str().to_string()));
+        }
+        Ok(Self {
+            node_id,
+            pointer,
+        })
+    }
+
+    fn interpolation(&self, ctx: &mut ExecutionContext) -> Option<Interpolation> {
+        let pointer = self.pointer.resolve(|name| ctx.input_int(self.node_id, name))?;
+        let to = self.to(ctx)?;
+        let (duration, p1, p2) = easing_inputs(ctx, self.node_id)?;
+        if to.value_type() != self.pointer.value_type() || to.value_type() == ValueType::Bool {
+            return None;
+        }
+        ctx.pointer_policy.check_write(&pointer).ok()?;
+        let from = ctx.read_pointer(&pointer)?;
+        Some(Interpolation {
+            node_id: self.node_id,
+            slerp: self.pointer.quaternion(),
+            target: InterpolationTarget::Pointer(pointer),
+            from,
+            to,
+            duration,
+            elapsed: 0.0,
+            p1,
+            p2,
+        })
+    }
+}
+
+node! {
+    PointerInterpolateNode {
+        op: "pointer/interpolate",
+        kind: Flow,
+        description: "Moves a property to `value` over `duration` seconds.",
+        input_values: { to = "value": Value, duration: f32, p1: [f32; 2], p2: [f32; 2] },
+        input_flows: ["in"],
+        output_flows: ["out", "err", "done"],
+        configuration: |node| { "pointer": String(node.pointer.as_str().to_string()) },
+        new_node: |node_id, configuration| Self::new(node_id, configured_pointer(configuration)?)
+            .map_err(|a| ConfigurationError::invalid("pointer", a))?,
+        layout: |node, descriptor| with_parameters(descriptor, &node.pointer),
+    }
+    impl NodeBehavior {
+        fn value(&mut self, _: &mut ExecutionContext) -> Option<()> {
+            panic!("cannot request value from this node");
+        }
+
+        fn activate_input_node(&mut self, ctx: &mut ExecutionContext) -> Option<()> {
+            match self.interpolation(ctx) {
+                Some(interpolation) => {
+                    ctx.start_interpolation(interpolation);
+                    ctx.fire(self.node_id, "out")
+                }
+                None => ctx.fire(self.node_id, "err"),
+            }
+        }
+    }
+}
+
+/// `variable/interpolate`, moves `variable` to its `value` input over
+/// `duration` seconds and activates `out`, or `err` if the inputs are
+/// invalid. `done` activates once the variable reaches the value.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct VariableInterpolateNode {
+    node_id: NodeId,
+    variable: usize,
+}
+
+impl VariableInterpolateNode {
+    pub fn new(node_id: NodeId, variable: usize) -> Self {
+        Self {
+            node_id,
+            variable,
+        }
+    }
+
+    fn interpolation(&self, ctx: &ExecutionContext) -> Option<Interpolation> {
+        let to = self.to(ctx)?;
+        let (duration, p1, p2) = easing_inputs(ctx, self.node_id)?;
+        let from = ctx.variables.get(self.variable)?.clone();
+        lerp_value(&from, &to, 0.0)?;
+        Some(Interpolation {
+            node_id: self.node_id,
+            target: InterpolationTarget::Variable(self.variable),
+            from,
+            to,
+            duration,
+            elapsed: 0.0,
+            p1,
+            p2,
+            slerp: false,
+        })
+    }
+}
+
+node! {
+    VariableInterpolateNode {
+        op: "variable/interpolate",
+        kind: Flow,
+        description: "Moves a variable to `value` over `duration` seconds.",
+        input_values: { to = "value": Value, duration: f32, p1: [f32; 2], p2: [f32; 2] },
+        input_flows: ["in"],
+        output_flows: ["out", "err", "done"],
+        configuration: |node| { "variable": Int(node.variable as i32) },
+        new_node: |node_id, configuration, declarations| Self::new(node_id, declared_variable("variable", configuration.index("variable")?.unwrap_or(0), declarations)?),
+    }
+    impl NodeBehavior {
+        fn value(&mut self, _: &mut ExecutionContext) -> Option<()> {
+            panic!("cannot request value from this node");
+        }
+
+        fn activate_input_node(&mut self, ctx: &mut ExecutionContext) -> Option<()> {
+            match self.interpolation(ctx) {
+                Some(interpolation) => {
+                    ctx.start_interpolation(interpolation);
+                    ctx.fire(self.node_id, "out")
+                }
+                None => ctx.fire(self.node_id, "err"),
+            }
+        }
+    }
+}
+
+#[test]
+fn test_easing() {
+    assert!((cubic_bezier([0.0, 0.0], [1.0, 1.0], 0.25) - 0.25).abs() < 1e-5);
+    assert!(cubic_bezier([0.42, 0.0], [0.58, 1.0], 0.25) < 0.25);
+    assert!((cubic_bezier([0.42, 0.0], [0.58, 1.0], 0.5) - 0.5).abs() < 1e-5);
+
+    let half = std::f32::consts::FRAC_1_SQRT_2;
+    let mut rotation = Interpolation {
+        node_id: 0,
+        target: InterpolationTarget::Pointer("/nodes/0/rotation".to_string()),
+        from: Value::Float4([0.0, 0.0, 0.0, 1.0]),
+        to: Value::Float4([0.0, 0.0, half, half]),
+        duration: 2.0,
+        elapsed: 0.0,
+        p1: [0.0, 0.0],
+        p2: [1.0, 1.0],
+        slerp: true,
+    };
+    let (Value::Float4(value), false) = rotation.advance(1.0) else {
+        panic!("rotation finished early");
+    };
+    let eighth = std::f32::consts::FRAC_PI_8;
+    assert!(value.iter().zip([0.0, 0.0, eighth.sin(), eighth.cos()]).all(|(a, b)| (a - b).abs() < 1e-5));
+    assert_eq!(rotation.advance(1.0), (Value::Float4([0.0, 0.0, half, half]), true));
+    assert_eq!(lerp_value(&Value::Int(0), &Value::Int(3), 0.5), Some(Value::Int(2)));
+    assert_eq!(lerp_value(&Value::Bool(false), &Value::Bool(true), 0.5), None);
+}
+
+#[test]
+fn test_variable_interpolate() {
+    use std::collections::HashMap;
+    use crate::{CollectingLogSink, EventDeclaration, EventReceiveNode, Graph, LogNode, NodeBehavior, NodeBehaviors, OnStartNode, Runtime, Severity, VariableDeclaration, VariableGetNode, VariableSetNode};
+
+    let declarations = [
+        ("x", Value::Float(0.0)),
+        ("target", Value::Float(1.0)),
+        ("duration", Value::Float(1.0)),
+        ("p1", Value::Float2([0.0, 0.0])),
+        ("p2", Value::Float2([1.0, 1.0])),
+    ];
+    let stop = EventDeclaration {
+        id: "stop".to_string(),
+        values: vec![],
+    };
+
+    // onStart interpolates x to 1 and logs once done, the stop event sets x to 1 right away.
+    let build = || {
+        let mut graph = Graph::new();
+        let mut node_behaviors = NodeBehaviors(HashMap::new());
+
+        let start_id = 0;
+        let interpolate_id = 1;
+        let log_id = 2;
+        let receive_id = 3;
+        let set_id = 4;
+        let constant_ids = [5, 6, 7, 8];
+
+        let start = OnStartNode::new(start_id);
+        let interpolate = VariableInterpolateNode::new(interpolate_id, 0);
+        let log = LogNode::new(log_id, "done", Severity::Info);
+        let receive = EventReceiveNode::new(receive_id, 0, &stop);
+        let set = VariableSetNode::new(set_id, 0);
+
+        graph
+            .add_node(start.node())
+            .add_node(interpolate.node())
+            .add_node(log.node())
+            .add_node(receive.node())
+            .add_node(set.node())
+            .link_flow(start_id, "out", interpolate_id, "in")
+            .link_flow(interpolate_id, "done", log_id, "in")
+            .link_flow(receive_id, "out", set_id, "in")
+            .link_value(constant_ids[0], "value", set_id, "value");
+
+        node_behaviors.add(Box::new(start));
+        node_behaviors.add(Box::new(interpolate));
+        node_behaviors.add(Box::new(log));
+        node_behaviors.add(Box::new(receive));
+        node_behaviors.add(Box::new(set));
+        for (i, (node_id, socket)) in constant_ids.into_iter().zip(["value", "duration", "p1", "p2"]).enumerate() {
+            let constant = VariableGetNode::new(node_id, i + 1);
+            graph
+                .add_node(constant.node())
+                .link_value(node_id, "value", interpolate_id, socket);
+            node_behaviors.add(Box::new(constant));
+        }
+
+        let sink = CollectingLogSink::default();
+        let mut runtime = Runtime::new(graph.resolve().unwrap(), node_behaviors);
+        runtime.set_log_sink(sink.clone());
+        for (name, value) in &declarations {
+            runtime.add_variable(VariableDeclaration {
+                name: name.to_string(),
+                value_type: value.value_type(),
+                value: value.clone(),
+            }).unwrap();
+        }
+        runtime.add_event(stop.clone());
+        (runtime, sink)
+    };
+
+    let (mut runtime, sink) = build();
+    runtime.start();
+    assert_eq!(runtime.interpolations().len(), 1);
+    runtime.tick(0.5);
+    assert_eq!(runtime.variable(0), Some(&Value::Float(0.5)));
+    assert!(sink.messages().is_empty());
+    runtime.tick(0.5);
+    assert_eq!(runtime.variable(0), Some(&Value::Float(1.0)));
+    assert_eq!(sink.messages(), vec!["done"]);
+    assert!(runtime.interpolations().is_empty());
+
+    let (mut runtime, sink) = build();
+    runtime.tick(0.5);
+    runtime.send_event(0, HashMap::new()).unwrap();
+    runtime.tick(0.25);
+    assert!(runtime.interpolations().is_empty());
+    assert_eq!(runtime.variable(0), Some(&Value::Float(1.0)));
+    runtime.tick(1.0);
+    assert!(sink.messages().is_empty());
+}
@@ -1,196 +1,363 @@
 use std::cell::OnceCell;
-use std::collections::HashMap;
-use std::sync::{Arc, Mutex, OnceLock};
+use std::collections::{HashMap, VecDeque};
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone)]
+pub mod animation;
+pub mod configuration;
+pub mod debug;
+pub mod descriptor;
+pub mod event;
+pub mod gltf;
+pub mod graph;
+pub mod interpolate;
+pub mod log;
+pub mod node;
+pub mod pointer;
+pub mod record;
+pub mod registry;
+pub mod runtime;
+pub mod selection;
+pub mod snapshot;
+pub mod trace;
+pub mod variable;
+pub mod world;
+
+pub use animation::{AnimationStartNode, AnimationStopAtNode, AnimationStopNode};
+pub use configuration::{Configuration, ConfigurationError, ConfigurationValue};
+pub use debug::Breakpoint;
+pub use descriptor::{ConfigurationDescriptor, ConfigurationType, NodeDescriptor, NodeKind, ValueSocketDescriptor};
+pub use event::{CustomEvent, EventDeclaration, EventError, EventReceiveNode, EventSendNode, EventValue, OnStartNode, OnTickNode, RuntimeEvent};
+pub use gltf::{GltfError, GltfWorld, InteractivityError};
+pub use graph::{Declarations, ExecutableGraph, Graph, GraphError, Link, Node, SocketRef};
+pub use interpolate::{Interpolation, InterpolationTarget, PointerInterpolateNode, VariableInterpolateNode};
+pub use log::{CollectingLogSink, LogEntry, LogNode, LogSink, Severity, StdoutLogSink};
+pub use node::SocketValue;
+pub use pointer::{PointerError, PointerGetNode, PointerPolicy, PointerSetNode, PointerTemplate};
+pub use record::{HostInput, Outputs, PointerWrite, RecordedInput, Recording, ReplayError};
+pub use registry::{get_registry, LoadError, NodeRegistry, RegisterNode};
+pub use runtime::{Completion, PendingFlow, Runtime};
+pub use selection::{OnHoverInNode, OnHoverOutNode, OnSelectNode};
+pub use snapshot::{Snapshot, SnapshotError};
+pub use trace::{ChromeTraceSink, TraceEvent, TraceKind, TraceSink, Tracer};
+pub use variable::{VariableDeclaration, VariableError, VariableGetNode, VariableSetMultipleNode, VariableSetNode, Variables};
+pub use world::{EmptyWorld, World, WorldEvent};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Value {
     Float(f32),
     Bool(bool),
     Int(i32),
+    Float2([f32; 2]),
+    Float3([f32; 3]),
+    Float4([f32; 4]),
+    /// A column-major 4x4 matrix.
+    Float4x4([f32; 16]),
+}
+
+impl Value {
+    pub fn value_type(&self) -> ValueType {
+        match self {
+            Value::Float(_) => ValueType::Float,
+            Value::Bool(_) => ValueType::Bool,
+            Value::Int(_) => ValueType::Int,
+            Value::Float2(_) => ValueType::Float2,
+            Value::Float3(_) => ValueType::Float3,
+            Value::Float4(_) => ValueType::Float4,
+            Value::Float4x4(_) => ValueType::Float4x4,
+        }
+    }
 }
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ValueType {
     Float,
     Bool,
     Int,
+    Float2,
+    Float3,
+    Float4,
+    Float4x4,
+}
+
+impl ValueType {
+    /// The value a socket of this type holds when nothing has been written to it.
+    pub fn default_value(&self) -> Value {
+        match self {
+            ValueType::Float => Value::Float(0.0),
+            ValueType::Bool => Value::Bool(false),
+            ValueType::Int => Value::Int(0),
+            ValueType::Float2 => Value::Float2([0.0; 2]),
+            ValueType::Float3 => Value::Float3([0.0; 3]),
+            ValueType::Float4 => Value::Float4([0.0; 4]),
+            ValueType::Float4x4 => Value::Float4x4([0.0; 16]),
+        }
+    }
 }
 
 pub type NodeId = u32;
 
 pub trait NodeArchetypeBuilder: NodeBehavior {
-    fn build(node_id: NodeId) -> NodeArchetypeIncomplete;
-    fn new_node(node_id: NodeId) -> Box<dyn NodeBehavior>;
+    /// The op, sockets and configuration of the node type.
+    fn descriptor() -> NodeDescriptor;
+    /// A node of the type as it appears in a [`Graph`], laid out for `configuration`.
+    fn build(node_id: NodeId, configuration: &Configuration, declarations: &Declarations) -> Result<Node, ConfigurationError> {
+        Ok(Self::new_node(node_id, configuration, declarations)?.node())
+    }
+    /// Creates a node from its configuration, which must only hold entries the
+    /// [`NodeDescriptor`] of the type declares. Entries left out take defaults.
+    /// Variables and events the configuration refers to are looked up in
+    /// `declarations`, those of the graph the node belongs to.
+    fn new_node(node_id: NodeId, configuration: &Configuration, declarations: &Declarations) -> Result<Box<dyn NodeBehavior>, ConfigurationError>;
     fn name() -> String;
 }
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct NodeArchetypeIncomplete {
-    pub node_id: NodeId,
-    pub name: String,
-    pub input_value_sockets: Vec<InputValueSocketIncomplete>,
-    pub input_flow_sockets: Vec<InputFlowSocketIncomplete>,
-    pub output_value_sockets: Vec<OutputValueSocketIncomplete>,
-    pub output_flow_sockets: Vec<OutputFlowSocketIncomplete>,
-}
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct InputValueSocketIncomplete {
-    name: String,
-    pub output_value_socket: Option<crate::OutputValueSocketIncomplete>,
-}
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct OutputValueSocketIncomplete {
-    name: String,
-    node_id: NodeId,
-}
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct InputFlowSocketIncomplete {
-    name: String,
-    node_id: NodeId,
-    pub output_flow_socket: Option<crate::OutputFlowSocketIncomplete>,
-}
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct OutputFlowSocketIncomplete {
-    name: String,
-    pub input_flow_socket: Option<Box<crate::InputFlowSocketIncomplete>>,
-}
 
-impl From<NodeArchetypeIncomplete> for NodeArchetype {
-    fn from(value: NodeArchetypeIncomplete) -> Self {
-        NodeArchetype {
-            node_id: value.node_id,
-            name: value.name,
-            input_value_sockets: value.input_value_sockets.into_iter().map(|a| a.into()).collect(),
-            input_flow_sockets: value.input_flow_sockets.into_iter().map(|a| a.into()).collect(),
-            output_value_sockets: value.output_value_sockets.into_iter().map(|a| a.into()).collect(),
-            output_flow_sockets: value.output_flow_sockets.into_iter().map(|a| a.into()).collect(),
-        }
-    }
-}
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ExistingValues(HashMap<NodeId, HashMap<String, Value>>);
 
-impl From<OutputFlowSocketIncomplete> for OutputFlowSocket {
-    fn from(value: OutputFlowSocketIncomplete) -> OutputFlowSocket {
-        OutputFlowSocket {
-            name: value.name,
-            input_flow_socket: match value.input_flow_socket {
-                None => None,
-                Some(some) => {
-                    Some(Box::new((*some).into()))
-                }
-            },
-        }
+impl Default for ExistingValues {
+    fn default() -> Self {
+        Self(HashMap::default())
     }
 }
 
-impl From<InputFlowSocketIncomplete> for InputFlowSocket {
-    fn from(value: InputFlowSocketIncomplete) -> Self {
-        InputFlowSocket {
-            name: value.name,
-            node_id: value.node_id,
-            output_flow_socket: value.output_flow_socket.map(|a| a.into()),
-        }
+impl ExistingValues {
+    /// Computes the requested values outside of a [`Runtime`].
+    pub fn run(&mut self, request: Vec<Request>, graph: &ExecutableGraph, node_behaviors: &mut NodeBehaviors) {
+        self.standalone(graph, node_behaviors, |ctx| ctx.run(request));
+    }
+    /// Activates a node outside of a [`Runtime`].
+    pub fn flow(&mut self, request: NodeId, graph: &ExecutableGraph, node_behaviors: &mut NodeBehaviors) {
+        self.standalone(graph, node_behaviors, |ctx| ctx.flow(request));
+    }
+    /// Lends the values to a context without a runtime behind it: there are no
+    /// graph variables and no world, logs go to stdout, and custom events sent
+    /// or flows deferred while it runs are dropped.
+    fn standalone(&mut self, graph: &ExecutableGraph, node_behaviors: &mut NodeBehaviors, f: impl FnOnce(&mut ExecutionContext)) {
+        let mut ctx = ExecutionContext {
+            graph,
+            existing_values: self,
+            node_behaviors,
+            variables: &mut Variables::default(),
+            events: &mut VecDeque::new(),
+            pending_flows: &mut vec![],
+            interpolations: &mut vec![],
+            world: &mut EmptyWorld,
+            pointer_policy: &PointerPolicy::default(),
+            log: &mut StdoutLogSink,
+            tracer: &mut Tracer::default(),
+            scheduled: &mut vec![],
+        };
+        f(&mut ctx);
     }
-}
-
-
-impl From<InputValueSocketIncomplete> for InputValueSocket {
-    fn from(value: InputValueSocketIncomplete) -> InputValueSocket {
-        InputValueSocket {
-            name: value.name,
-            output_value_socket: value.output_value_socket.unwrap().into(),
-        }
+    pub fn get_value(&self, node_id: NodeId, name: &str) -> Option<&Value> {
+        self.0.get(&node_id)?.get(name)
     }
-}
-
-
-impl From<OutputValueSocketIncomplete> for OutputValueSocket {
-    fn from(value: OutputValueSocketIncomplete)-> OutputValueSocket {
-        OutputValueSocket {
-            name: value.name,
-            node_id: value.node_id,
+    pub fn set_value(&mut self, node_id: NodeId, name: impl ToString, value: Value) -> Option<()> {
+        if self.0.get(&node_id).is_none() {
+            self.0.insert(node_id, HashMap::new());
         }
+        let temp = self.0.get_mut(&node_id).unwrap();
+        temp.insert(name.to_string(), value);
+        Some(())
     }
 }
 
-
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct NodeArchetype {
+/// Everything a [`NodeBehavior`] can reach while it computes its values or is
+/// activated.
+pub struct ExecutionContext<'a> {
+    pub graph: &'a ExecutableGraph,
+    pub existing_values: &'a mut ExistingValues,
+    pub node_behaviors: &'a mut NodeBehaviors,
+    pub variables: &'a mut Variables,
+    /// Custom events sent by `event/send` nodes, picked up by the runtime.
+    pub events: &'a mut VecDeque<CustomEvent>,
+    /// Output flows waiting for something to happen outside the graph.
+    pub pending_flows: &'a mut Vec<PendingFlow>,
+    /// Interpolations the runtime advances on every tick.
+    pub interpolations: &'a mut Vec<Interpolation>,
+    pub world: &'a mut dyn World,
+    pub pointer_policy: &'a PointerPolicy,
+    pub log: &'a mut dyn LogSink,
+    /// Told about activations, computed values, fired flows and pointer writes.
+    pub tracer: &'a mut Tracer,
+    /// Activations waiting to run, the next one last. Firing an output flow
+    /// socket only schedules what it is linked to, so that execution can be
+    /// paused between any two activations.
+    pub scheduled: &'a mut Vec<Activation>,
+}
+
+/// An input flow waiting to be activated.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Activation {
     pub node_id: NodeId,
-    pub name: String,
-    pub input_value_sockets: Vec<InputValueSocket>,
-    pub input_flow_sockets: Vec<InputFlowSocket>,
-    pub output_value_sockets: Vec<OutputValueSocket>,
-    pub output_flow_sockets: Vec<OutputFlowSocket>,
-}
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct InputValueSocket {
-    name: String,
-    output_value_socket: OutputValueSocket,
-}
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct OutputValueSocket {
-    name: String,
-    node_id: NodeId,
-}
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct InputFlowSocket {
-    name: String,
-    node_id: NodeId,
-    output_flow_socket: Option<OutputFlowSocket>,
-}
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct OutputFlowSocket {
-    name: String,
-    input_flow_socket: Option<Box<InputFlowSocket>>,
+    /// The node and name of the output flow socket that fired, unless the
+    /// node was activated directly.
+    pub fired_by: Option<(NodeId, String)>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct NodeArchetypes(pub HashMap<NodeId, NodeArchetype>);
-
-impl NodeArchetypes {
-    pub fn add_archetype(&mut self, input_value_nodes: &[OutputValueSocket], input_flow_nodes: &[OutputFlowSocket], node: &dyn NodeBehavior) {
-        let archetype = node.create_node_archetype(input_value_nodes, input_flow_nodes);
-        self.0.insert(node.node_id(), archetype);
+impl ExecutionContext<'_> {
+    pub fn run(&mut self, request: Vec<Request>) {
+        for Request { node_id } in request {
+            // A node that is being activated right now has already written the
+            // values it exposes, so they are read as they are.
+            let Some(behavior) = self.node_behaviors.0.get(&node_id) else {
+                continue;
+            };
+            let requests = behavior.request(self.graph);
+            self.run(requests);
+            let mut behavior = self.node_behaviors.0.remove(&node_id).unwrap();
+            if behavior.value(self).is_none() {
+                self.report_failure(node_id, "compute its values");
+            }
+            self.node_behaviors.0.insert(node_id, behavior);
+            self.trace_values(node_id);
+        }
     }
-}
-
-
-pub struct ExistingValues(HashMap<NodeId, HashMap<String, Value>>);
-
-impl Default for ExistingValues {
-    fn default() -> Self {
-        Self(HashMap::default())
+    /// Activates a node, then everything that activation fires, depth first.
+    pub fn flow(&mut self, request: NodeId) {
+        let depth = self.scheduled.len();
+        self.scheduled.push(Activation {
+            node_id: request,
+            fired_by: None,
+        });
+        while self.scheduled.len() > depth {
+            self.step();
+        }
     }
-}
-
-impl ExistingValues {
-    pub fn run(&mut self, request: Vec<Request>, node_archetypes: &NodeArchetypes, node_behaviors: &mut NodeBehaviors) {
-        for Request { node_id } in request {
-            let behavior = node_behaviors.0.get(&node_id).unwrap();
-            let requests = behavior.request(node_archetypes);
-            drop(behavior);
-            self.run(requests, node_archetypes, node_behaviors);
-            let behavior = node_behaviors.0.get_mut(&node_id).unwrap();
-            behavior.value(node_archetypes, self).unwrap();
+    /// Runs the next scheduled activation. The flows it fires run next, in the
+    /// order they fired.
+    pub fn step(&mut self) -> Option<Activation> {
+        let activation = self.scheduled.pop()?;
+        let node_id = activation.node_id;
+        self.tracer.trace(|| TraceKind::NodeActivated {
+            node_id,
+            op: self.graph.node(node_id).map(|a| a.op.clone()).unwrap_or_default(),
+        });
+        let Some(behavior) = self.node_behaviors.0.get(&node_id) else {
+            self.report_failure(node_id, "be activated");
+            return Some(activation);
+        };
+        let requests = behavior.request(self.graph);
+        self.run(requests);
+        let depth = self.scheduled.len();
+        let mut behavior = self.node_behaviors.0.remove(&node_id).unwrap();
+        if behavior.activate_input_node(self).is_none() {
+            // A failed activation fires nothing, not even what it fired before failing.
+            self.scheduled.truncate(depth);
+            self.report_failure(node_id, "be activated");
+        }
+        self.node_behaviors.0.insert(node_id, behavior);
+        self.scheduled[depth..].reverse();
+        Some(activation)
+    }
+    /// Logs that `node_id` returned `None` instead of doing `what`.
+    fn report_failure(&mut self, node_id: NodeId, what: &str) {
+        let op = self.graph.node(node_id).map(|a| a.op.as_str()).unwrap_or_default();
+        self.log.log(Severity::Error, &format!("node {node_id} ({op}) failed to {what}"));
+    }
+    /// Traces the values `node_id` exposes after computing them.
+    fn trace_values(&mut self, node_id: NodeId) {
+        let Some(node) = self.graph.node(node_id) else {
+            return;
+        };
+        for socket in &node.output_value_sockets {
+            if let Some(value) = self.existing_values.get_value(node_id, socket) {
+                self.tracer.trace(|| TraceKind::ValueComputed {
+                    node_id,
+                    socket: socket.clone(),
+                    value: value.clone(),
+                });
+            }
+        }
+    }
+    /// Schedules whatever is linked to the output flow socket `name` of `node_id`.
+    pub fn fire(&mut self, node_id: NodeId, name: &str) -> Option<()> {
+        let node = self.graph.node(node_id)?;
+        if !node.output_flow_sockets.iter().any(|a| a == name) {
+            return None;
+        }
+        self.tracer.trace(|| TraceKind::FlowFired {
+            node_id,
+            socket: name.to_string(),
+        });
+        if let Some(input_flow_socket) = self.graph.flow(node_id, name) {
+            self.scheduled.push(Activation {
+                node_id: input_flow_socket.node_id,
+                fired_by: Some((node_id, name.to_string())),
+            });
         }
+        Some(())
     }
-    pub fn flow(&mut self, request: NodeId, node_archetypes: &NodeArchetypes, node_behaviors: &mut NodeBehaviors) {
-        let behavior = node_behaviors.0.get(&request).unwrap();
-        let requests = behavior.request(node_archetypes);
-        drop(behavior);
-        self.run(requests, node_archetypes, node_behaviors);
-        let mut behavior = node_behaviors.0.remove(&request).unwrap();
-        behavior.activate_input_node(node_archetypes, self, node_behaviors).unwrap();
-        node_behaviors.0.insert(request, behavior);
+    /// The value linked to the input value socket `name` of `node_id`.
+    pub fn input_value(&self, node_id: NodeId, name: &str) -> Option<Value> {
+        match self.graph.input(node_id, name) {
+            Some(output_value_socket) => self.existing_values.get_value(output_value_socket.node_id, &output_value_socket.name).cloned(),
+            None => self.graph.node(node_id)?.values.get(name).cloned(),
+        }
     }
-    pub fn set_value(&mut self, node_id: NodeId, name: impl ToString, value: Value) -> Option<()> {
-        if self.0.get(&node_id).is_none() {
-            self.0.insert(node_id, HashMap::new());
+    pub fn input_int(&self, node_id: NodeId, name: &str) -> Option<i32> {
+        match self.input_value(node_id, name)? {
+            Value::Int(value) => Some(value),
+            _ => None,
         }
-        let temp = self.0.get_mut(&node_id).unwrap();
-        temp.insert(name.to_string(), value);
+    }
+    pub fn input_float(&self, node_id: NodeId, name: &str) -> Option<f32> {
+        match self.input_value(node_id, name)? {
+            Value::Float(value) => Some(value),
+            _ => None,
+        }
+    }
+    /// Reads a resolved pointer from the world if the pointer policy allows it.
+    pub fn read_pointer(&mut self, pointer: &str) -> Option<Value> {
+        if let Err(error) = self.pointer_policy.check_read(pointer) {
+            self.log.log(Severity::Warning, &error.to_string());
+            return None;
+        }
+        self.world.read_pointer(pointer)
+    }
+    /// Writes a resolved pointer to the world if the pointer policy allows it,
+    /// cancelling any interpolation of the same pointer.
+    pub fn write_pointer(&mut self, pointer: &str, value: Value) -> Option<()> {
+        if let Err(error) = self.pointer_policy.check_write(pointer) {
+            self.log.log(Severity::Warning, &error.to_string());
+            return None;
+        }
+        self.world.write_pointer(pointer, value.clone())?;
+        self.tracer.trace(|| TraceKind::PointerWritten {
+            pointer: pointer.to_string(),
+            value,
+        });
+        self.cancel_interpolation(&InterpolationTarget::Pointer(pointer.to_string()));
         Some(())
     }
+    /// Starts an interpolation in place of any other of the same target.
+    pub fn start_interpolation(&mut self, interpolation: Interpolation) {
+        self.cancel_interpolation(&interpolation.target);
+        self.interpolations.push(interpolation);
+    }
+    /// Drops the interpolation of `target`, if any, without activating its `done`.
+    pub fn cancel_interpolation(&mut self, target: &InterpolationTarget) {
+        self.interpolations.retain(|a| &a.target != target);
+    }
+    /// Activates the output flow socket `name` of `node_id` once `completion`
+    /// is reported to the runtime, instead of right away.
+    pub fn defer(&mut self, node_id: NodeId, name: &str, completion: Completion) {
+        self.pending_flows.push(PendingFlow {
+            node_id,
+            name: name.to_string(),
+            completion,
+        });
+    }
+    /// Drops every deferred flow waiting for `completion`.
+    pub fn cancel(&mut self, completion: &Completion) {
+        self.pending_flows.retain(|a| &a.completion != completion);
+    }
+    /// Sets a graph variable, cancelling any interpolation of the variable.
+    pub fn set_variable(&mut self, index: usize, value: Value) -> Result<(), VariableError> {
+        self.variables.set(index, value)?;
+        self.cancel_interpolation(&InterpolationTarget::Variable(index));
+        Ok(())
+    }
 }
 
 pub struct NodeBehaviors(pub HashMap<NodeId, Box<dyn NodeBehavior>>);
@@ -201,27 +368,61 @@ impl NodeBehaviors {
     }
 }
 
-struct Request {
-    node_id: NodeId,
+pub struct Request {
+    pub node_id: NodeId,
 }
 
 struct Response(Value);
 
 pub trait NodeBehavior {
     fn node_id(&self) -> NodeId;
-    fn create_node_archetype(&self, input_value_nodes: &[OutputValueSocket], input_flow_nodes: &[OutputFlowSocket]) -> NodeArchetype;
-    fn request(&self, node_archetypes: &NodeArchetypes) -> Vec<Request> {
-        let mut requests = vec![];
-        for input_value_socket in node_archetypes.0.get(&self.node_id()).unwrap().input_value_sockets.iter() {
-            requests.push(Request {
-                node_id: input_value_socket.output_value_socket.node_id,
-            });
-        }
-        requests
+    /// The descriptor of the node type, laid out for this node's configuration:
+    /// e.g. a `debug/log` node has an input value socket per placeholder.
+    fn layout(&self) -> NodeDescriptor;
+    /// What [`NodeArchetypeBuilder::new_node`] creates this node from.
+    fn configuration(&self) -> Configuration {
+        Configuration::new()
+    }
+    /// The node as it appears in a [`Graph`], with the sockets it has.
+    fn node(&self) -> Node {
+        let mut node = self.layout().node(self.node_id());
+        node.configuration = self.configuration();
+        node
+    }
+    fn request(&self, graph: &ExecutableGraph) -> Vec<Request> {
+        graph.inputs(self.node_id()).map(|a| Request {
+            node_id: a.node_id,
+        }).collect()
+    }
+    /// Computes the output values. `None` means the node could not, e.g.
+    /// because an input holds a value of the wrong type: the runtime logs an
+    /// error and its outputs keep whatever they held.
+    fn value(&mut self, ctx: &mut ExecutionContext) -> Option<()>;
+
+    /// Runs the node when one of its input flow sockets is activated. `None`
+    /// means it failed: the runtime logs an error and nothing it fired runs.
+    /// Nodes that can say what went wrong log it themselves and return `Some`.
+    fn activate_input_node(&mut self, ctx: &mut ExecutionContext) -> Option<()>;
+
+    /// Called by the [`Runtime`] for every event it dispatches. Event nodes react
+    /// to the events they listen for, everything else ignores them.
+    fn handle_event(&mut self, _ctx: &mut ExecutionContext, _event: &RuntimeEvent) -> Option<()> {
+        Some(())
+    }
+    /// Whether a selection or hover event must not propagate further up the
+    /// node hierarchy once this node has handled it.
+    fn stops_propagation(&self, _event: &RuntimeEvent) -> bool {
+        false
+    }
+    /// What the node keeps between activations, such as a counter, for
+    /// [`Runtime::snapshot`]. Nodes without such state return `None`.
+    fn state(&self) -> Option<serde_json::Value> {
+        None
+    }
+    /// Puts back the state [`NodeBehavior::state`] returned.
+    fn restore_state(&mut self, _state: serde_json::Value) -> Result<(), serde_json::Error> {
+        Ok(())
     }
-    fn value(&mut self, node_archetypes: &NodeArchetypes, existing_values: &mut ExistingValues) -> Option<()>;
-
-    fn activate_input_node(&mut self, node_archetypes: &NodeArchetypes, existing_values: &mut ExistingValues, node_behaviors: &mut NodeBehaviors) -> Option<()>;
 }
 #[derive(Clone, Serialize, Deserialize)]
 pub struct MathPi {
@@ -235,61 +436,23 @@ impl MathPi {
     }
 }
 
-impl NodeArchetypeBuilder for MathPi {
-    fn build(node_id: NodeId) -> NodeArchetypeIncomplete {
-        NodeArchetypeIncomplete {
-            node_id,
-            name: Self::name(),
-            input_value_sockets: vec![],
-            input_flow_sockets: vec![],
-            output_value_sockets: vec![
-                OutputValueSocketIncomplete {
-                    name: "value".to_string(),
-                    node_id,
-                }
-            ],
-            output_flow_sockets: vec![],
-        }
-    }
-
-    fn new_node(node_id: NodeId) -> Box<dyn NodeBehavior> {
-        Box::new(Self::new(node_id))
-    }
-
-    fn name() -> String {
-        "math/pi".to_string()
+node! {
+    MathPi {
+        op: "math/pi",
+        kind: Pure,
+        description: "Exposes π.",
+        output_values: { set_value = "value": f32 },
+        new_node: |node_id| Self::new(node_id),
     }
-}
-
-impl NodeBehavior for MathPi {
-    fn node_id(&self) -> NodeId {
-        self.node_id
-    }
-
-    fn create_node_archetype(&self, _: &[OutputValueSocket], _: &[OutputFlowSocket]) -> NodeArchetype {
-        NodeArchetype {
-            node_id: self.node_id,
-            name: "math/pi".to_string(),
-            input_value_sockets: vec![],
-            input_flow_sockets: vec![],
-            output_value_sockets: vec![i
-                OutputValueSocket {
-                    name: "value".to_string(),
-                    node_id: self.node_id,
-                }
-            ],
-            output_flow_sockets: vec![],
+    impl NodeBehavior {
+        fn value(&mut self, ctx: &mut ExecutionContext) -> Option<()> {
+            self.set_value(ctx, std::f32::consts::PI)
         }
-    }
-
-
-    fn value(&mut self, node_archetypes: &NodeArchetypes, existing_values: &mut ExistingValues) -> Option<()> {
-        existing_values.set_value(self.node_id, "value", Value::Float(std::f32::consts::PI));
-        Some(())
-    }
 
-    fn activate_input_node(&mut self, node_archetypes: &NodeArchetypes, existing_values: &mut ExistingValues, _: &mut NodeBehaviors) -> Option<()> {
-        todo!()
+        fn activate_input_node(&mut self, _: &mut ExecutionContext) -> Option<()> {
+            // A pure node has no input flow, so there is nothing to activate.
+            None
+        }
     }
 }
 #[derive(Clone, Serialize, Deserialize)]
@@ -305,98 +468,25 @@ impl MathAdd {
     }
 }
 
-impl NodeArchetypeBuilder for MathAdd {
-    fn build(node_id: NodeId) -> NodeArchetypeIncomplete {
-        NodeArchetypeIncomplete {
-            node_id,
-            name: Self::name(),
-            input_value_sockets: vec![
-                InputValueSocketIncomplete {
-                    name: "a".to_string(),
-                    output_value_socket: None,
-                },
-                InputValueSocketIncomplete {
-                    name: "b".to_string(),
-                    output_value_socket: None,
-                }
-            ],
-            input_flow_sockets: vec![],
-            output_value_sockets: vec![
-                OutputValueSocketIncomplete {
-                    name: "value".to_string(),
-                    node_id,
-                }
-            ],
-            output_flow_sockets: vec![],
-        }
-    }
-
-    fn new_node(node_id: NodeId) -> Box<dyn NodeBehavior> {
-        Box::new(Self::new(node_id))
-    }
-
-    fn name() -> String {
-        "math/add".to_string()
+node! {
+    MathAdd {
+        op: "math/add",
+        kind: Pure,
+        description: "Adds `a` and `b`.",
+        input_values: { a: f32, b: f32 },
+        output_values: { set_value = "value": f32 },
+        new_node: |node_id| Self::new(node_id),
     }
-}
-
-impl NodeBehavior for MathAdd {
-    fn node_id(&self) -> NodeId {
-        self.node_id
-    }
-
-    fn create_node_archetype(&self, input_value_nodes: &[OutputValueSocket], _: &[OutputFlowSocket]) -> NodeArchetype {
-        NodeArchetype {
-            node_id: self.node_id,
-            name: "math/add".to_string(),
-            input_value_sockets: vec![
-                InputValueSocket {
-                    name: "a".to_string(),
-                    output_value_socket: input_value_nodes.get(0).unwrap().clone(),
-                },
-                InputValueSocket {
-                    name: "b".to_string(),
-                    output_value_socket: input_value_nodes.get(1).unwrap().clone(),
-                }
-            ],
-            input_flow_sockets: vec![],
-            output_value_sockets: vec![
-                OutputValueSocket {
-                    name: "value".to_string(),
-                    node_id: self.node_id,
-                }
-            ],
-            output_flow_sockets: vec![],
+    impl NodeBehavior {
+        fn value(&mut self, ctx: &mut ExecutionContext) -> Option<()> {
+            let sum = self.a(ctx)? + self.b(ctx)?;
+            self.set_value(ctx, sum)
         }
-    }
-
-    fn value(&mut self, node_archetypes: &NodeArchetypes, existing_values: &mut ExistingValues) -> Option<()> {
-        let archetype = node_archetypes.0.get(&self.node_id())?;
-        let a = archetype.input_value_sockets.get(0)?;
-        let a = &a.output_value_socket;
-        let val = existing_values.0.get(&a.node_id)?;
-        let a = val.get(&a.name)?.clone();
-
-        let b = archetype.input_value_sockets.get(0)?;
-        let b = &b.output_value_socket;
-        let val = existing_values.0.get(&b.node_id)?;
-        let b = val.get(&b.name)?.clone();
-
-        let a = match a {
-            Value::Float(a) => a,
-            _ => panic!(),
-        };
-        let b = match b {
-            Value::Float(b) => b,
-            _ => panic!(),
-        };
-
-        existing_values.set_value(self.node_id,"value", Value::Float(a + b))?;
-        Some(())
-    }
 
-    fn activate_input_node(&mut self, node_archetypes: &NodeArchetypes, existing_values: &mut ExistingValues, _: &mut NodeBehaviors) -> Option<()> {
-        todo!()
+        fn activate_input_node(&mut self, _: &mut ExecutionContext) -> Option<()> {
+            // A pure node has no input flow, so there is nothing to activate.
+            None
+        }
     }
 }
 
@@ -413,191 +503,89 @@ impl PrintNode {
     }
 }
 
-impl NodeArchetypeBuilder for PrintNode {
-    fn build(node_id: NodeId) -> NodeArchetypeIncomplete {
-        NodeArchetypeIncomplete {
-            node_id,
-            name: Self::name(),
-            input_value_sockets: vec![
-                InputValueSocketIncomplete {
-                    name: "print_value".to_string(),
-                    output_value_socket: None,
-                }
-            ],
-            input_flow_sockets: vec![
-                InputFlowSocketIncomplete {
-                    name: "print_input".to_string(),
-                    node_id,
-                    output_flow_socket: None,
-                }
-            ],
-            output_value_sockets: vec![],
-            output_flow_sockets: vec![],
-        }
-    }
-
-    fn new_node(node_id: NodeId) -> Box<dyn NodeBehavior> {
-        Box::new(Self::new(node_id))
+node! {
+    PrintNode {
+        op: "custom/print",
+        kind: Flow,
+        description: "Logs `print_value`.",
+        input_values: { print_value: Value },
+        input_flows: ["print_input"],
+        new_node: |node_id| Self::new(node_id),
     }
-
-    fn name() -> String {
-        "custom/print".to_string()
-    }
-}
-
-impl NodeBehavior for PrintNode {
-    fn node_id(&self) -> NodeId {
-        self.node_id
-    }
-
-    fn create_node_archetype(&self, input_value_nodes: &[OutputValueSocket], input_flow_nodes: &[OutputFlowSocket]) -> NodeArchetype {
-        NodeArchetype {
-            node_id: self.node_id,
-            name: "custom/print".to_string(),
-            input_value_sockets: vec![
-                InputValueSocket {
-                    name: "print_value".to_string(),
-                    output_value_socket: input_value_nodes.first().unwrap().clone(),
-                }
-            ],
-            input_flow_sockets: vec![
-                InputFlowSocket {
-                    name: "print_input".to_string(),
-                    node_id: self.node_id,
-                    output_flow_socket: input_flow_nodes.first().map(|a| a.clone()),
-                }
-            ],
-            output_value_sockets: vec![],
-            output_flow_sockets: vec![],
+    impl NodeBehavior {
+        fn value(&mut self, _: &mut ExecutionContext) -> Option<()> {
+            Some(())
         }
-    }
-
-    fn value(&mut self, node_archetypes: &NodeArchetypes, existing_values: &mut ExistingValues) -> Option<()> {
-        Some(())
-    }
 
-    fn activate_input_node(&mut self, node_archetypes: &NodeArchetypes, existing_values: &mut ExistingValues, _: &mut NodeBehaviors) -> Option<()> {
-        let archetype = node_archetypes.0.get(&self.node_id)?;
-        let a = archetype.input_value_sockets.get(0)?;
-        let a = &a.output_value_socket;
-        let val = existing_values.0.get(&a.node_id)?;
-        let a = val.get(&a.name)?.clone();
-        println!("{:#?}", a);
-        Some(())
+        fn activate_input_node(&mut self, ctx: &mut ExecutionContext) -> Option<()> {
+            let value = self.print_value(ctx)?;
+            ctx.log.log(Severity::Info, &format!("{:#?}", value));
+            Some(())
+        }
     }
 }
 
+/// `flow/sequence`, activates each of `outputs` in order.
 #[derive(Clone, Serialize, Deserialize)]
 pub struct SequenceNode {
-    node_id: NodeId
+    node_id: NodeId,
+    outputs: Vec<String>,
 }
 
 impl SequenceNode {
-    pub fn new(node_id: NodeId) -> Self {
+    pub fn new(node_id: NodeId, outputs: &[&str]) -> Self {
         Self {
             node_id,
+            outputs: outputs.iter().map(|a| a.to_string()).collect(),
         }
     }
-}
 
-impl NodeArchetypeBuilder for SequenceNode {
-    fn build(node_id: NodeId) -> NodeArchetypeIncomplete {
-        NodeArchetypeIncomplete {
-            node_id,
-            name: Self::name(),
-            input_value_sockets: vec![],
-            input_flow_sockets: vec![
-                InputFlowSocketIncomplete {
-                    name: "in".to_string(),
-                    node_id,
-                    output_flow_socket: None,
-                }
-            ],
-            output_value_sockets: vec![],
-            output_flow_sockets: vec![
-                OutputFlowSocketIncomplete {
-                    name: "out".to_string(),
-                    input_flow_socket: None,
-                }
-            ],
+    /// The node for an `outputs` configuration entry, one output flow per
+    /// name, or a single `out` without one.
+    fn configured(node_id: NodeId, outputs: Option<Vec<String>>) -> Result<Self, ConfigurationError> {
+        let outputs = outputs.unwrap_or_else(|| vec!["out".to_string()]);
+        if let Some(name) = outputs.iter().enumerate().find_map(|(i, a)| outputs[..i].contains(a).then_some(a)) {
+            return Err(ConfigurationError::invalid("outputs", format!("the output `{name}` is given twice")));
         }
-    }
-
-    fn new_node(node_id: NodeId) -> Box<dyn NodeBehavior> {
-        Box::new(Self::new(node_id))
-    }
-
-    fn name() -> String {
-        "flow/sequence".to_string()
-    }
-}
-
-impl NodeBehavior for SequenceNode {
-    fn node_id(&self) -> NodeId {
-        self.node_id
-    }
-
-    fn create_node_archetype(&self, input_value_nodes: &[OutputValueSocket], input_flow_nodes: &[OutputFlowSocket]) -> NodeArchetype {
-        NodeArchetype {
-            node_id: self.node_id,
-            name: "flow/sequence".to_string(),
-            input_value_sockets: vec![],
-            input_flow_sockets: vec![
-                InputFlowSocket {
-                    name: "in".to_string(),
-                    node_id: self.node_id,
-                    output_flow_socket: input_flow_nodes.first().map(|a| a.clone()),
-                }
-            ],
-            output_value_sockets: vec![],
-            output_flow_sockets: input_flow_nodes.to_vec(),
+        Ok(Self {
+            node_id,
+            outputs,
+        })
+    }
+}
+
+node! {
+    SequenceNode {
+        op: "flow/sequence",
+        kind: Flow,
+        description: "Activates each of its output flows in order.",
+        input_flows: ["in"],
+        output_flows: ["out"],
+        configuration: |node| { "outputs": StringArray(node.outputs.clone()) },
+        new_node: |node_id, configuration| Self::configured(node_id, configuration.string_array("outputs")?)?,
+        layout: |node, descriptor| NodeDescriptor {
+            output_flow_sockets: node.outputs.clone(),
+            ..descriptor
+        },
+    }
+    impl NodeBehavior {
+        fn value(&mut self, _: &mut ExecutionContext) -> Option<()> {
+            panic!("cannot request value from this node");
         }
-    }
-
-    fn value(&mut self, node_archetypes: &NodeArchetypes, existing_values: &mut ExistingValues) -> Option<()> {
-        panic!("cannot request value from this node");
-    }
 
-    fn activate_input_node(&mut self, node_archetypes: &NodeArchetypes, existing_values: &mut ExistingValues, node_behaviors: &mut NodeBehaviors) -> Option<()> {
-        let node_archetype = node_archetypes.0.get(&self.node_id)?;
-        for output_flow_socket in &node_archetype.output_flow_sockets {
-            if let Some(input_flow_socket) = output_flow_socket.input_flow_socket.as_ref() {
-                existing_values.flow(input_flow_socket.node_id, node_archetypes, node_behaviors);
+        fn activate_input_node(&mut self, ctx: &mut ExecutionContext) -> Option<()> {
+            for name in &self.outputs {
+                ctx.fire(self.node_id, name);
             }
+            Some(())
         }
-        Some(())
-    }
-}
-
-static REGISTRY: OnceLock<Arc<Mutex<HashMap<String, (Box<fn(NodeId) -> NodeArchetypeIncomplete>, Box<fn(NodeId) -> Box<dyn NodeBehavior>>)>>>> = OnceLock::new();
-
-pub fn get_registry() -> Arc<Mutex<HashMap<String, (Box<fn(NodeId) -> NodeArchetypeIncomplete>, Box<fn(NodeId) -> Box<dyn NodeBehavior>>)>>> {
-    REGISTRY.get_or_init(|| {
-        Arc::new(Mutex::new(HashMap::default()))
-    }).clone()
-}
-
-pub trait RegisterNode {
-    fn register();
-}
-
-impl<T: NodeArchetypeBuilder + NodeBehavior> RegisterNode for T {
-    fn register() {
-        get_registry()
-            .lock().unwrap()
-            .insert(T::name(), (Box::new(|node_id| {
-                T::build(node_id)
-            }), Box::new(|node_id| {
-                T::new_node(node_id)
-            })));
     }
 }
 
-
 #[test]
 fn test() {
     let mut existing_values = ExistingValues(Default::default());
-    let mut archetypes = NodeArchetypes(HashMap::new());
+    let mut graph = Graph::new();
     let mut node_behaviors = NodeBehaviors(HashMap::new());
 
     let pi_id = 0;
@@ -608,17 +596,14 @@ fn test() {
     let pi = MathPi::new(pi_id);
     let add = MathAdd::new(add_id);
     let print = PrintNode::new(print_id);
-    archetypes.add_archetype(&[], &[], &pi);
-    let pi_output = OutputValueSocket {
-        name: "value".to_string(),
-        node_id: pi_id,
-    };
-    let add_output = OutputValueSocket {
-        name: "value".to_string(),
-        node_id: add_id,
-    };
-    archetypes.add_archetype(&[pi_output.clone(), pi_output.clone()], &[], &add);
-    archetypes.add_archetype(&[add_output.clone()], &[], &print);
+    graph
+        .add_node(pi.node())
+        .add_node(add.node())
+        .add_node(print.node())
+        .link_value(pi_id, "value", add_id, "a")
+        .link_value(pi_id, "value", add_id, "b")
+        .link_value(add_id, "value", print_id, "print_value");
+    let graph = graph.resolve().unwrap();
 
     node_behaviors.add(Box::new(pi));
     node_behaviors.add(Box::new(add));
@@ -627,7 +612,7 @@ fn test() {
     let request = Request {
         node_id: add_id,
     };
-    existing_values.run(vec![request], &archetypes, &mut node_behaviors);
+    existing_values.run(vec![request], &graph, &mut node_behaviors);
     panic!("value is: {:#?}", existing_values.0.get(&1).unwrap().get("value").unwrap());
 }
 
@@ -635,7 +620,7 @@ fn test() {
 #[test]
 fn test2() {
     let mut existing_values = ExistingValues(Default::default());
-    let mut archetypes = NodeArchetypes(HashMap::new());
+    let mut graph = Graph::new();
     let mut node_behaviors = NodeBehaviors(HashMap::new());
 
     let pi_id = 0;
@@ -646,29 +631,26 @@ fn test2() {
     let pi = MathPi::new(pi_id);
     let add = MathAdd::new(add_id);
     let print = PrintNode::new(print_id);
-    archetypes.add_archetype(&[], &[], &pi);
-    let pi_output = OutputValueSocket {
-        name: "value".to_string(),
-        node_id: pi_id,
-    };
-    let add_output = OutputValueSocket {
-        name: "value".to_string(),
-        node_id: add_id,
-    };
-    archetypes.add_archetype(&[pi_output.clone(), pi_output.clone()], &[], &add);
-    archetypes.add_archetype(&[add_output.clone()], &[], &print);
+    graph
+        .add_node(pi.node())
+        .add_node(add.node())
+        .add_node(print.node())
+        .link_value(pi_id, "value", add_id, "a")
+        .link_value(pi_id, "value", add_id, "b")
+        .link_value(add_id, "value", print_id, "print_value");
+    let graph = graph.resolve().unwrap();
 
     node_behaviors.add(Box::new(pi));
     node_behaviors.add(Box::new(add));
     node_behaviors.add(Box::new(print));
 
-    existing_values.flow(print_id, &archetypes, &mut node_behaviors);
+    existing_values.flow(print_id, &graph, &mut node_behaviors);
 }
 
 #[test]
 fn test3() {
     let mut existing_values = ExistingValues(Default::default());
-    let mut archetypes = NodeArchetypes(HashMap::new());
+    let mut graph = Graph::new();
     let mut node_behaviors = NodeBehaviors(HashMap::new());
 
     let pi_id = 0;
@@ -682,38 +664,21 @@ fn test3() {
     let add = MathAdd::new(add_id);
     let print = PrintNode::new(print_id);
     let print2 = PrintNode::new(print2_id);
-    let seq = SequenceNode::new(seq_id);
-
-    let pi_output = OutputValueSocket {
-        name: "value".to_string(),
-        node_id: pi_id,
-    };
-    let add_output = OutputValueSocket {
-        name: "value".to_string(),
-        node_id: add_id,
-    };
-    let seq_output1 = OutputFlowSocket {
-        name: "first".to_string(),
-        input_flow_socket: Some(Box::new(InputFlowSocket {
-            name: "print_input".to_string(),
-            node_id: print_id,
-            output_flow_socket: None,
-        })),
-    };
-    let seq_output2 = OutputFlowSocket {
-        name: "second".to_string(),
-        input_flow_socket: Some(Box::new(InputFlowSocket {
-            name: "print_input".to_string(),
-            node_id: print2_id,
-            output_flow_socket: None,
-        })),
-    };
-    archetypes.add_archetype(&[], &[], &pi);
-    archetypes.add_archetype(&[pi_output.clone(), pi_output.clone()], &[], &add);
-    archetypes.add_archetype(&[add_output.clone()], &[], &print);
-    archetypes.add_archetype(&[pi_output.clone()], &[], &print2);
-
-    archetypes.add_archetype(&[], &[seq_output1, seq_output2], &seq);
+    let seq = SequenceNode::new(seq_id, &["first", "second"]);
+
+    graph
+        .add_node(pi.node())
+        .add_node(add.node())
+        .add_node(print.node())
+        .add_node(print2.node())
+        .add_node(seq.node())
+        .link_value(pi_id, "value", add_id, "a")
+        .link_value(pi_id, "value", add_id, "b")
+        .link_value(add_id, "value", print_id, "print_value")
+        .link_value(pi_id, "value", print2_id, "print_value")
+        .link_flow(seq_id, "first", print_id, "print_input")
+        .link_flow(seq_id, "second", print2_id, "print_input");
+    let graph = graph.resolve().unwrap();
 
     node_behaviors.add(Box::new(pi));
     node_behaviors.add(Box::new(add));
@@ -721,5 +686,37 @@ fn test3() {
     node_behaviors.add(Box::new(print2));
     node_behaviors.add(Box::new(seq));
 
-    existing_values.flow(seq_id, &archetypes, &mut node_behaviors);
-}
\ No newline at end of file
+    existing_values.flow(seq_id, &graph, &mut node_behaviors);
+}
+
+#[test]
+fn test_sequence_outputs() {
+    let registry = NodeRegistry::builtin();
+    let mut graph = Graph::new();
+    let seq = SequenceNode::new(0, &["first", "second"]);
+    graph
+        .add_node(seq.node())
+        .add_node(PrintNode::new(1).node())
+        .add_node(PrintNode::new(2).node())
+        .add_node(MathPi::new(3).node())
+        .link_value(3, "value", 1, "print_value")
+        .link_value(3, "value", 2, "print_value")
+        .link_flow(0, "first", 1, "print_input")
+        .link_flow(0, "second", 2, "print_input");
+
+    // The outputs are part of the configuration, so the registry lays them
+    // out like the node itself does.
+    assert_eq!(graph.nodes[0].configuration, Configuration::new().with("outputs", vec!["first".to_string(), "second".to_string()]));
+    registry.validate(&graph).unwrap();
+    registry.load(&graph).unwrap();
+    let mut document = serde_json::json!({});
+    gltf::write_graph(&graph, &registry, &mut document).unwrap();
+    let read = gltf::read_graph(&document.to_string(), &registry).unwrap();
+    assert_eq!(read.nodes, graph.nodes);
+
+    assert_eq!(SequenceNode::build(0, &Configuration::new(), &Declarations::new()).unwrap().output_flow_sockets, vec!["out"]);
+    assert!(matches!(
+        SequenceNode::new_node(0, &Configuration::new().with("outputs", vec!["a".to_string(), "a".to_string()]), &Declarations::new()),
+        Err(ConfigurationError::Invalid { .. })
+    ));
+}
@@ -0,0 +1,255 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use serde::{Deserialize, Serialize};
+use crate::{node, ExecutionContext, NodeId, Value};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    /// The severity of the spec's integer `severity` configuration. Unknown
+    /// values are treated as errors.
+    pub fn from_int(severity: i32) -> Self {
+        match severity {
+            0 => Severity::Info,
+            1 => Severity::Warning,
+            _ => Severity::Error,
+        }
+    }
+
+    pub fn to_int(self) -> i32 {
+        match self {
+            Severity::Info => 0,
+            Severity::Warning => 1,
+            Severity::Error => 2,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LogEntry {
+    pub severity: Severity,
+    pub message: String,
+}
+
+/// Where the messages of `debug/log` and `custom/print` end up.
+pub trait LogSink {
+    fn log(&mut self, severity: Severity, message: &str);
+}
+
+/// Writes every message to stdout, or stderr for warnings and errors.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StdoutLogSink;
+
+impl LogSink for StdoutLogSink {
+    fn log(&mut self, severity: Severity, message: &str) {
+        match severity {
+            Severity::Info => println!("{message}"),
+            Severity::Warning => eprintln!("warning: {message}"),
+            Severity::Error => eprintln!("error: {message}"),
+        }
+    }
+}
+
+/// Keeps every message. Clones share the same entries, so a clone can be
+/// handed to the runtime while the original is used to read them back.
+#[derive(Debug, Clone, Default)]
+pub struct CollectingLogSink(Rc<RefCell<Vec<LogEntry>>>);
+
+impl CollectingLogSink {
+    pub fn entries(&self) -> Vec<LogEntry> {
+        self.0.borrow().clone()
+    }
+
+    pub fn messages(&self) -> Vec<String> {
+        self.0.borrow().iter().map(|a| a.message.clone()).collect()
+    }
+
+    pub fn clear(&self) {
+        self.0.borrow_mut().clear();
+    }
+}
+
+impl LogSink for CollectingLogSink {
+    fn log(&mut self, severity: Severity, message: &str) {
+        self.0.borrow_mut().push(LogEntry {
+            severity,
+            message: message.to_string(),
+        });
+    }
+}
+
+/// A value the way `debug/log` writes it into a message.
+pub fn format_value(value: &Value) -> String {
+    fn list(values: &[f32]) -> String {
+        let values = values.iter().map(|a| a.to_string()).collect::<Vec<_>>();
+        format!("[{}]", values.join(", "))
+    }
+    match value {
+        Value::Float(a) => a.to_string(),
+        Value::Bool(a) => a.to_string(),
+        Value::Int(a) => a.to_string(),
+        Value::Float2(a) => list(a),
+        Value::Float3(a) => list(a),
+        Value::Float4(a) => list(a),
+        Value::Float4x4(a) => list(a),
+    }
+}
+
+enum MessagePart<'a> {
+    Text(&'a str),
+    Placeholder(&'a str),
+}
+
+/// Splits a message into literal text and `{placeholder}`s. A `{` without a
+/// matching `}` is literal text.
+fn message_parts(message: &str) -> Vec<MessagePart<'_>> {
+    let mut parts = vec![];
+    let mut rest = message;
+    while let Some(start) = rest.find('{') {
+        let Some(length) = rest[start..].find('}') else {
+            break;
+        };
+        if start > 0 {
+            parts.push(MessagePart::Text(&rest[..start]));
+        }
+        parts.push(MessagePart::Placeholder(&rest[start + 1..start + length]));
+        rest = &rest[start + length + 1..];
+    }
+    if !rest.is_empty() {
+        parts.push(MessagePart::Text(rest));
+    }
+    parts
+}
+
+/// The names of the `{placeholder}`s of a message, each once, in order.
+pub fn message_placeholders(message: &str) -> Vec<String> {
+    let mut placeholders: Vec<String> = vec![];
+    for part in message_parts(message) {
+        if let MessagePart::Placeholder(name) = part {
+            if !placeholders.iter().any(|a| a == name) {
+                placeholders.push(name.to_string());
+            }
+        }
+    }
+    placeholders
+}
+
+/// `debug/log`, writes `message` to the runtime's log sink and activates
+/// `out`. Every `{placeholder}` of the message becomes an input value socket
+/// and is replaced by its value; unlinked placeholders are left as they are.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct LogNode {
+    node_id: NodeId,
+    message: String,
+    severity: Severity,
+}
+
+impl LogNode {
+    pub fn new(node_id: NodeId, message: impl ToString, severity: Severity) -> Self {
+        Self {
+            node_id,
+            message: message.to_string(),
+            severity,
+        }
+    }
+
+    fn format(&self, ctx: &ExecutionContext) -> String {
+        message_parts(&self.message)
+            .into_iter()
+            .map(|part| match part {
+                MessagePart::Text(text) => text.to_string(),
+                MessagePart::Placeholder(name) => match ctx.input_value(self.node_id, name) {
+                    Some(value) => format_value(&value),
+                    None => format!("{{{name}}}"),
+                },
+            })
+            .collect()
+    }
+}
+
+node! {
+    LogNode {
+        op: "debug/log",
+        kind: Flow,
+        description: "Logs a message, replacing each `{placeholder}` by the input of the same name.",
+        input_flows: ["in"],
+        output_flows: ["out"],
+        configuration: |node| { "message": String(node.message.clone()), "severity": Int(node.severity.to_int()) },
+        new_node: |node_id, configuration| Self::new(
+            node_id,
+            configuration.string("message")?.unwrap_or_default(),
+            Severity::from_int(configuration.int("severity")?.unwrap_or(0)),
+        ),
+        layout: |node, descriptor| message_placeholders(&node.message).iter().fold(descriptor, |a, b| a.input_any(b)),
+    }
+    impl NodeBehavior {
+        fn value(&mut self, _: &mut ExecutionContext) -> Option<()> {
+            panic!("cannot request value from this node");
+        }
+
+        fn activate_input_node(&mut self, ctx: &mut ExecutionContext) -> Option<()> {
+            let message = self.format(ctx);
+            ctx.log.log(self.severity, &message);
+            ctx.fire(self.node_id, "out")
+        }
+    }
+}
+
+#[test]
+fn test_log() {
+    use std::collections::HashMap;
+    use crate::{Graph, MathPi, NodeBehavior, NodeBehaviors, OnStartNode, PrintNode, Runtime};
+
+    assert_eq!(message_placeholders("{a} and {b}, {a} again {"), vec!["a", "b"]);
+
+    let mut graph = Graph::new();
+    let mut node_behaviors = NodeBehaviors(HashMap::new());
+
+    let start_id = 0;
+    let log_id = 1;
+    let pi_id = 2;
+    let print_id = 3;
+
+    let start = OnStartNode::new(start_id);
+    let log = LogNode::new(log_id, "pi is {pi}, {answer} is not linked", Severity::Warning);
+    let pi = MathPi::new(pi_id);
+    let print = PrintNode::new(print_id);
+
+    // Nothing is linked to `{answer}`, so the node's own value fills it in.
+    graph
+        .add_node(start.node())
+        .add_node(log.node().with_value("answer", Value::Int(42)))
+        .add_node(pi.node())
+        .add_node(print.node())
+        .link_flow(start_id, "out", log_id, "in")
+        .link_flow(log_id, "out", print_id, "print_input")
+        .link_value(pi_id, "value", log_id, "pi")
+        .link_value(pi_id, "value", print_id, "print_value");
+
+    node_behaviors.add(Box::new(start));
+    node_behaviors.add(Box::new(log));
+    node_behaviors.add(Box::new(pi));
+    node_behaviors.add(Box::new(print));
+
+    let sink = CollectingLogSink::default();
+    let mut runtime = Runtime::new(graph.resolve().unwrap(), node_behaviors);
+    runtime.set_log_sink(sink.clone());
+    runtime.start();
+
+    let pi = format_value(&Value::Float(std::f32::consts::PI));
+    assert_eq!(sink.entries(), vec![
+        LogEntry {
+            severity: Severity::Warning,
+            message: format!("pi is {pi}, 42 is not linked"),
+        },
+        LogEntry {
+            severity: Severity::Info,
+            message: format!("{:#?}", Value::Float(std::f32::consts::PI)),
+        },
+    ]);
+}
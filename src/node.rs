@@ -0,0 +1,219 @@
+use crate::{Value, ValueType};
+
+/// A Rust type the value sockets declared with [`node!`] hold.
+pub trait SocketValue: Sized {
+    /// `None` for [`Value`], which sockets of any type hold.
+    const VALUE_TYPE: Option<ValueType>;
+    fn from_value(value: Value) -> Option<Self>;
+    fn into_value(self) -> Value;
+}
+
+macro_rules! socket_value {
+    ($($rust_type:ty => $value_type:ident),*) => {
+        $(
+            impl SocketValue for $rust_type {
+                const VALUE_TYPE: Option<ValueType> = Some(ValueType::$value_type);
+
+                fn from_value(value: Value) -> Option<Self> {
+                    match value {
+                        Value::$value_type(value) => Some(value),
+                        _ => None,
+                    }
+                }
+
+                fn into_value(self) -> Value {
+                    Value::$value_type(self)
+                }
+            }
+        )*
+    };
+}
+
+socket_value!(f32 => Float, bool => Bool, i32 => Int, [f32; 2] => Float2, [f32; 3] => Float3, [f32; 4] => Float4, [f32; 16] => Float4x4);
+
+impl SocketValue for Value {
+    const VALUE_TYPE: Option<ValueType> = None;
+
+    fn from_value(value: Value) -> Option<Self> {
+        Some(value)
+    }
+
+    fn into_value(self) -> Value {
+        self
+    }
+}
+
+/// Declares a node type once: its op, sockets and configuration, next to its
+/// [`crate::NodeBehavior`]. The struct must have a `node_id` field. The macro
+/// implements [`crate::NodeArchetypeBuilder`], which also makes the type
+/// [`crate::RegisterNode`], and the `node_id`, `layout` and `configuration` of
+/// [`crate::NodeBehavior`] around the methods given in `impl NodeBehavior`.
+///
+/// Every value socket is declared as `accessor = "socketName": Type`, or as
+/// `accessor: Type` if the socket is named like the accessor, and gets a
+/// typed accessor: a getter for inputs and a setter for outputs. Sockets of
+/// [`Value`] take any type.
+///
+/// Every configuration entry is declared with its type and how to read it
+/// from a node, and `new_node` reads them back from a
+/// [`crate::Configuration`], with `?` for invalid entries. It may also take
+/// the [`crate::Declarations`] of the graph, to look up what an entry refers to. Nodes whose
+/// sockets depend on their configuration give a `layout`, which turns the
+/// descriptor of the type into that of the node.
+///
+/// ```ignore
+/// node! {
+///     VariableGetNode {
+///         op: "variable/get",
+///         kind: Pure,
+///         description: "Exposes the current value of a variable.",
+///         output_values: { set_value = "value": Value },
+///         configuration: |node| { "variable": Int(node.variable as i32) },
+///         new_node: |node_id, configuration, declarations| Self::new(node_id, declared_variable("variable", configuration.index("variable")?.unwrap_or(0), declarations)?),
+///     }
+///     impl NodeBehavior {
+///         fn value(&mut self, ctx: &mut ExecutionContext) -> Option<()> {
+///             let value = ctx.variables.get(self.variable)?.clone();
+///             self.set_value(ctx, value)
+///         }
+///         // ...
+///     }
+/// }
+/// ```
+#[macro_export]
+macro_rules! node {
+    (
+        $node:ident {
+            op: $op:literal,
+            kind: $kind:ident,
+            description: $description:literal,
+            $(input_values: { $($input:ident $(= $input_socket:literal)?: $input_type:ty),* $(,)? },)?
+            $(output_values: { $($output:ident $(= $output_socket:literal)?: $output_type:ty),* $(,)? },)?
+            $(input_flows: [$($input_flow:literal),* $(,)?],)?
+            $(output_flows: [$($output_flow:literal),* $(,)?],)?
+            $(configuration: |$configured:ident| { $($configuration:literal: $configuration_type:ident($configuration_value:expr)),* $(,)? },)?
+            new_node: |$new_node_id:ident $(, $new_node_configuration:ident $(, $new_node_declarations:ident)?)?| $new_node:expr,
+            $(layout: |$this:ident, $descriptor:ident| $layout:expr,)?
+        }
+        impl NodeBehavior {
+            $($behavior:tt)*
+        }
+    ) => {
+        impl $crate::NodeArchetypeBuilder for $node {
+            fn descriptor() -> $crate::NodeDescriptor {
+                #[allow(unused_mut)]
+                let mut descriptor = $crate::NodeDescriptor::new($op, $crate::NodeKind::$kind, $description);
+                $($(
+                    descriptor.input_value_sockets.push($crate::ValueSocketDescriptor {
+                        name: $crate::node!(@name $input $($input_socket)?).to_string(),
+                        value_type: <$input_type as $crate::SocketValue>::VALUE_TYPE,
+                    });
+                )*)?
+                $($(
+                    descriptor.output_value_sockets.push($crate::ValueSocketDescriptor {
+                        name: $crate::node!(@name $output $($output_socket)?).to_string(),
+                        value_type: <$output_type as $crate::SocketValue>::VALUE_TYPE,
+                    });
+                )*)?
+                $($(descriptor = descriptor.input_flow($input_flow);)*)?
+                $($(descriptor = descriptor.output_flow($output_flow);)*)?
+                $($(descriptor = descriptor.configuration($configuration, $crate::ConfigurationType::$configuration_type);)*)?
+                descriptor
+            }
+
+            fn new_node($new_node_id: $crate::NodeId, configuration: &$crate::Configuration, _declarations: &$crate::Declarations) -> Result<Box<dyn $crate::NodeBehavior>, $crate::ConfigurationError> {
+                configuration.check(&<Self as $crate::NodeArchetypeBuilder>::descriptor())?;
+                $(
+                    let $new_node_configuration = configuration;
+                    $(let $new_node_declarations = _declarations;)?
+                )?
+                Ok(Box::new($new_node))
+            }
+
+            fn name() -> String {
+                $op.to_string()
+            }
+        }
+
+        #[allow(dead_code)]
+        impl $node {
+            $($(
+                fn $input(&self, ctx: &$crate::ExecutionContext) -> Option<$input_type> {
+                    let value = ctx.input_value(self.node_id, $crate::node!(@name $input $($input_socket)?))?;
+                    <$input_type as $crate::SocketValue>::from_value(value)
+                }
+            )*)?
+            $($(
+                fn $output(&self, ctx: &mut $crate::ExecutionContext, value: $output_type) -> Option<()> {
+                    let name = $crate::node!(@name $output $($output_socket)?);
+                    ctx.existing_values.set_value(self.node_id, name, $crate::SocketValue::into_value(value))
+                }
+            )*)?
+        }
+
+        impl $crate::NodeBehavior for $node {
+            fn node_id(&self) -> $crate::NodeId {
+                self.node_id
+            }
+
+            fn layout(&self) -> $crate::NodeDescriptor {
+                let descriptor = <Self as $crate::NodeArchetypeBuilder>::descriptor();
+                $(let descriptor = {
+                    let $this = self;
+                    let $descriptor = descriptor;
+                    $layout
+                };)?
+                descriptor
+            }
+
+            $(fn configuration(&self) -> $crate::Configuration {
+                let $configured = self;
+                $crate::Configuration::new()
+                    $(.with($configuration, $crate::ConfigurationValue::$configuration_type($configuration_value)))*
+            })?
+
+            $($behavior)*
+        }
+    };
+    (@name $accessor:ident $socket:literal) => {
+        $socket
+    };
+    (@name $accessor:ident) => {
+        stringify!($accessor)
+    };
+}
+
+#[test]
+fn test_node_macro() {
+    use crate::{Configuration, Declarations, EventDeclaration, NodeArchetypeBuilder, NodeBehavior, NodeRegistry, SequenceNode, VariableDeclaration};
+
+    // The sockets a node gets are those its type declares.
+    let registry = NodeRegistry::builtin();
+    let mut declarations = Declarations::new();
+    declarations.events.push(EventDeclaration {
+        id: "ping".to_string(),
+        values: vec![],
+    });
+    declarations.variables.push(VariableDeclaration {
+        name: "x".to_string(),
+        value_type: ValueType::Float,
+        value: Value::Float(0.0),
+    });
+    for descriptor in registry.descriptors() {
+        let node = registry.new_node(&descriptor.op, 0, &Configuration::new(), &declarations).unwrap().node();
+        let expected = descriptor.node(0);
+        assert_eq!(node.op, expected.op);
+        assert_eq!(node.output_value_sockets, expected.output_value_sockets);
+        assert_eq!(node.input_flow_sockets, expected.input_flow_sockets);
+        assert_eq!(node.output_flow_sockets, expected.output_flow_sockets);
+    }
+
+    let sequence = SequenceNode::new(0, &["a", "b"]);
+    assert_eq!(sequence.node().output_flow_sockets, vec!["a", "b"]);
+    assert_eq!(SequenceNode::descriptor().output_flow_sockets, vec!["out"]);
+
+    assert_eq!(f32::from_value(Value::Float(1.0)), Some(1.0));
+    assert_eq!(f32::from_value(Value::Int(1)), None);
+    assert_eq!(<[f32; 3]>::VALUE_TYPE, Some(ValueType::Float3));
+    assert_eq!(Value::VALUE_TYPE, None);
+}
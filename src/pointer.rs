@@ -0,0 +1,618 @@
+use serde::{Deserialize, Serialize};
+use crate::{node, Configuration, ConfigurationError, ExecutionContext, NodeDescriptor, NodeId, Value, ValueSocketDescriptor, ValueType};
+
+/// A property pointers can address. `{}` stands for an array index.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PointerProperty {
+    pub template: &'static str,
+    pub value_type: ValueType,
+    pub read_only: bool,
+    /// Whether the property is a rotation quaternion, which interpolates
+    /// along the sphere rather than linearly.
+    pub quaternion: bool,
+}
+
+const fn property(template: &'static str, value_type: ValueType) -> PointerProperty {
+    PointerProperty {
+        template,
+        value_type,
+        read_only: false,
+        quaternion: false,
+    }
+}
+
+const fn read_only(template: &'static str, value_type: ValueType) -> PointerProperty {
+    PointerProperty {
+        template,
+        value_type,
+        read_only: true,
+        quaternion: false,
+    }
+}
+
+const fn quaternion(template: &'static str) -> PointerProperty {
+    PointerProperty {
+        template,
+        value_type: ValueType::Float4,
+        read_only: false,
+        quaternion: true,
+    }
+}
+
+/// The properties pointers can address, with the type of value they hold.
+pub const POINTER_PROPERTIES: &[PointerProperty] = &[
+    property("/nodes/{}/translation", ValueType::Float3),
+    quaternion("/nodes/{}/rotation"),
+    property("/nodes/{}/scale", ValueType::Float3),
+    read_only("/nodes/{}/matrix", ValueType::Float4x4),
+    property("/nodes/{}/weights/{}", ValueType::Float),
+    property("/nodes/{}/extensions/KHR_node_selectability/selectable", ValueType::Bool),
+    property("/nodes/{}/extensions/KHR_node_hoverability/hoverable", ValueType::Bool),
+    property("/nodes/{}/extensions/KHR_node_visibility/visible", ValueType::Bool),
+    property("/meshes/{}/weights/{}", ValueType::Float),
+    property("/materials/{}/pbrMetallicRoughness/baseColorFactor", ValueType::Float4),
+    property("/materials/{}/pbrMetallicRoughness/metallicFactor", ValueType::Float),
+    property("/materials/{}/pbrMetallicRoughness/roughnessFactor", ValueType::Float),
+    property("/materials/{}/emissiveFactor", ValueType::Float3),
+    property("/materials/{}/alphaCutoff", ValueType::Float),
+    property("/materials/{}/normalTexture/scale", ValueType::Float),
+    property("/materials/{}/occlusionTexture/strength", ValueType::Float),
+    property("/cameras/{}/perspective/aspectRatio", ValueType::Float),
+    property("/cameras/{}/perspective/yfov", ValueType::Float),
+    property("/cameras/{}/perspective/zfar", ValueType::Float),
+    property("/cameras/{}/perspective/znear", ValueType::Float),
+    property("/cameras/{}/orthographic/xmag", ValueType::Float),
+    property("/cameras/{}/orthographic/ymag", ValueType::Float),
+    property("/cameras/{}/orthographic/zfar", ValueType::Float),
+    property("/cameras/{}/orthographic/znear", ValueType::Float),
+    property("/extensions/KHR_lights_punctual/lights/{}/color", ValueType::Float3),
+    property("/extensions/KHR_lights_punctual/lights/{}/intensity", ValueType::Float),
+    property("/extensions/KHR_lights_punctual/lights/{}/range", ValueType::Float),
+    property("/extensions/KHR_lights_punctual/lights/{}/spot/innerConeAngle", ValueType::Float),
+    property("/extensions/KHR_lights_punctual/lights/{}/spot/outerConeAngle", ValueType::Float),
+];
+
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum PointerError {
+    #[error("pointer `{0}` does not start with `/`")]
+    NotAbsolute(String),
+    #[error("pointer `{pointer}` has an invalid segment `{segment}`")]
+    InvalidSegment {
+        pointer: String,
+        segment: String,
+    },
+    #[error("pointer `{pointer}` uses the parameter `{name}` more than once")]
+    DuplicateParameter {
+        pointer: String,
+        name: String,
+    },
+    #[error("pointer `{0}` does not address a known property")]
+    UnknownProperty(String),
+    #[error("pointer `{0}` addresses a read-only property")]
+    ReadOnly(String),
+    #[error("reading pointer `{0}` is not allowed")]
+    ReadBlocked(String),
+    #[error("writing pointer `{0}` is not allowed")]
+    WriteBlocked(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum PointerSegment {
+    Literal(String),
+    Parameter(String),
+}
+
+/// A pointer such as `/nodes/{nodeIndex}/rotation`, checked against
+/// [`POINTER_PROPERTIES`] when it is parsed. Each `{parameter}` segment stands
+/// for an array index supplied at runtime. Nodes are serialized with the
+/// pointer as text, so a graph with an invalid pointer fails to load.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct PointerTemplate {
+    pointer: String,
+    segments: Vec<PointerSegment>,
+    property: PointerProperty,
+}
+
+impl PointerTemplate {
+    pub fn parse(pointer: &str) -> Result<Self, PointerError> {
+        let Some(rest) = pointer.strip_prefix('/') else {
+            return Err(PointerError::NotAbsolute(pointer.to_string()));
+        };
+        let mut segments = vec![];
+        for segment in rest.split('/') {
+            let parameter = segment.strip_prefix('{').and_then(|a| a.strip_suffix('}'));
+            let segment = match parameter {
+                Some(name) if !name.is_empty() && !name.contains(['{', '}']) => {
+                    if segments.contains(&PointerSegment::Parameter(name.to_string())) {
+                        return Err(PointerError::DuplicateParameter {
+                            pointer: pointer.to_string(),
+                            name: name.to_string(),
+                        });
+                    }
+                    PointerSegment::Parameter(name.to_string())
+                }
+                None if !segment.is_empty() && !segment.contains(['{', '}']) => PointerSegment::Literal(segment.to_string()),
+                _ => return Err(PointerError::InvalidSegment {
+                    pointer: pointer.to_string(),
+                    segment: segment.to_string(),
+                }),
+            };
+            segments.push(segment);
+        }
+        let property = POINTER_PROPERTIES.iter()
+            .find(|property| {
+                let property_segments = property.template[1..].split('/').collect::<Vec<_>>();
+                property_segments.len() == segments.len() && property_segments.iter().zip(&segments).all(|pair| match pair {
+                    (&"{}", PointerSegment::Parameter(_)) => true,
+                    (&"{}", PointerSegment::Literal(segment)) => segment.parse::<u32>().is_ok(),
+                    (property_segment, PointerSegment::Literal(segment)) => property_segment == segment,
+                    _ => false,
+                })
+            })
+            .ok_or_else(|| PointerError::UnknownProperty(pointer.to_string()))?;
+        Ok(Self {
+            pointer: pointer.to_string(),
+            segments,
+            property: *property,
+        })
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.pointer
+    }
+
+    /// The type of the property the pointer addresses.
+    pub fn value_type(&self) -> ValueType {
+        self.property.value_type
+    }
+
+    pub fn read_only(&self) -> bool {
+        self.property.read_only
+    }
+
+    pub fn quaternion(&self) -> bool {
+        self.property.quaternion
+    }
+
+    /// The names of the `{parameter}` segments, in order. Each becomes an int
+    /// input value socket of the nodes using the pointer.
+    pub fn parameters(&self) -> Vec<String> {
+        self.segments.iter().filter_map(|segment| match segment {
+            PointerSegment::Parameter(name) => Some(name.clone()),
+            PointerSegment::Literal(_) => None,
+        }).collect()
+    }
+
+    /// Substitutes the `{parameter}` segments. Parameters must resolve to
+    /// array indices, so negative values fail.
+    pub fn resolve(&self, parameter: impl Fn(&str) -> Option<i32>) -> Option<String> {
+        let mut pointer = String::new();
+        for segment in &self.segments {
+            pointer.push('/');
+            match segment {
+                PointerSegment::Literal(segment) => pointer.push_str(segment),
+                PointerSegment::Parameter(name) => {
+                    let index = parameter(name)?;
+                    if index < 0 {
+                        return None;
+                    }
+                    pointer.push_str(&index.to_string());
+                }
+            }
+        }
+        Some(pointer)
+    }
+}
+
+impl TryFrom<String> for PointerTemplate {
+    type Error = PointerError;
+
+    fn try_from(pointer: String) -> Result<Self, Self::Error> {
+        Self::parse(&pointer)
+    }
+}
+
+impl From<PointerTemplate> for String {
+    fn from(template: PointerTemplate) -> Self {
+        template.pointer
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+enum Access {
+    All,
+    Only(Vec<String>),
+}
+
+impl Access {
+    fn allows(&self, pointer: &str) -> bool {
+        match self {
+            Access::All => true,
+            Access::Only(patterns) => patterns.iter().any(|pattern| pattern_matches(pattern, pointer)),
+        }
+    }
+
+    fn allow(&mut self, pattern: &str) {
+        if let Access::Only(patterns) = self {
+            patterns.push(pattern.to_string());
+        }
+    }
+}
+
+/// A `{}` segment of a pattern matches any array index.
+fn pattern_matches(pattern: &str, pointer: &str) -> bool {
+    let mut segments = pointer.split('/');
+    let mut pattern_segments = pattern.split('/');
+    loop {
+        match (segments.next(), pattern_segments.next()) {
+            (None, None) => return true,
+            (Some(segment), Some("{}")) if segment.parse::<u32>().is_ok() => {}
+            (Some(segment), Some(pattern_segment)) if segment == pattern_segment => {}
+            _ => return false,
+        }
+    }
+}
+
+/// Which resolved pointers a runtime lets its graph read and write, e.g. to
+/// sandbox graphs from untrusted files. Patterns are pointers in which a `{}`
+/// segment matches any array index, such as `/nodes/{}/translation`.
+/// Read-only properties can never be written, whatever the policy says.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PointerPolicy {
+    read: Access,
+    write: Access,
+}
+
+impl Default for PointerPolicy {
+    fn default() -> Self {
+        Self::allow_all()
+    }
+}
+
+impl PointerPolicy {
+    pub fn allow_all() -> Self {
+        Self {
+            read: Access::All,
+            write: Access::All,
+        }
+    }
+
+    /// Allows nothing until patterns are added with [`Self::allow_read`] and [`Self::allow_write`].
+    pub fn deny_all() -> Self {
+        Self {
+            read: Access::Only(vec![]),
+            write: Access::Only(vec![]),
+        }
+    }
+
+    /// Lets the graph read pointers matching `pattern`.
+    pub fn allow_read(mut self, pattern: &str) -> Self {
+        self.read.allow(pattern);
+        self
+    }
+
+    /// Lets the graph read and write pointers matching `pattern`.
+    pub fn allow_write(mut self, pattern: &str) -> Self {
+        self.read.allow(pattern);
+        self.write.allow(pattern);
+        self
+    }
+
+    pub fn check_read(&self, pointer: &str) -> Result<(), PointerError> {
+        match self.read.allows(pointer) {
+            true => Ok(()),
+            false => Err(PointerError::ReadBlocked(pointer.to_string())),
+        }
+    }
+
+    pub fn check_write(&self, pointer: &str) -> Result<(), PointerError> {
+        if PointerTemplate::parse(pointer).is_ok_and(|a| a.read_only()) {
+            return Err(PointerError::ReadOnly(pointer.to_string()));
+        }
+        match self.write.allows(pointer) {
+            true => Ok(()),
+            false => Err(PointerError::WriteBlocked(pointer.to_string())),
+        }
+    }
+}
+
+/// Resolves a pointer template against the node's int inputs of the same names.
+fn resolve_inputs(ctx: &ExecutionContext, node_id: NodeId, pointer: &PointerTemplate) -> Option<String> {
+    pointer.resolve(|name| ctx.input_int(node_id, name))
+}
+
+/// The descriptor of a pointer node with an int input value socket for every
+/// `{parameter}` of its pointer, ahead of the sockets of its type, and its
+/// `value` socket of the type of the pointer's property.
+pub(crate) fn with_parameters(mut descriptor: NodeDescriptor, pointer: &PointerTemplate) -> NodeDescriptor {
+    let sockets = descriptor.input_value_sockets.iter_mut().chain(&mut descriptor.output_value_sockets);
+    for socket in sockets.filter(|a| a.name == "value") {
+        socket.value_type = Some(pointer.value_type());
+    }
+    let parameters = pointer.parameters().into_iter().map(|name| ValueSocketDescriptor {
+        name,
+        value_type: Some(ValueType::Int),
+    });
+    descriptor.input_value_sockets.splice(0..0, parameters);
+    descriptor
+}
+
+/// The `pointer` configuration of a pointer node, the translation of a node
+/// if it is left out.
+pub(crate) fn configured_pointer(configuration: &Configuration) -> Result<PointerTemplate, ConfigurationError> {
+    let pointer = configuration.string("pointer")?.unwrap_or("/nodes/{nodeIndex}/translation");
+    PointerTemplate::parse(pointer).map_err(|a| ConfigurationError::invalid("pointer", a))
+}
+
+/// `pointer/get`, reads the property at `pointer` from the world. Every
+/// `{parameter}` of the pointer becomes an int input value socket.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PointerGetNode {
+    node_id: NodeId,
+    pointer: PointerTemplate,
+}
+
+impl PointerGetNode {
+    pub fn new(node_id: NodeId, pointer: PointerTemplate) -> Self {
+        Self {
+            node_id,
+            pointer,
+        }
+    }
+
+    /// The type of the `value` output, taken from the pointer's property.
+    pub fn value_type(&self) -> ValueType {
+        self.pointer.value_type()
+    }
+}
+
+node! {
+    PointerGetNode {
+        op: "pointer/get",
+        kind: Pure,
+        description: "Reads the property at a pointer.",
+        output_values: { set_value = "value": Value, set_is_valid = "isValid": bool },
+        configuration: |node| { "pointer": String(node.pointer.as_str().to_string()) },
+        new_node: |node_id, configuration| Self::new(node_id, configured_pointer(configuration)?),
+        layout: |node, descriptor| with_parameters(descriptor, &node.pointer),
+    }
+    impl NodeBehavior {
+        fn value(&mut self, ctx: &mut ExecutionContext) -> Option<()> {
+            let value = resolve_inputs(ctx, self.node_id, &self.pointer)
+                .and_then(|pointer| ctx.read_pointer(&pointer))
+                .filter(|value| value.value_type() == self.value_type());
+            let is_valid = value.is_some();
+            let value = value.unwrap_or_else(|| self.value_type().default_value());
+            self.set_value(ctx, value);
+            self.set_is_valid(ctx, is_valid)
+        }
+
+        fn activate_input_node(&mut self, _: &mut ExecutionContext) -> Option<()> {
+            panic!("cannot activate this node");
+        }
+    }
+}
+
+/// `pointer/set`, writes its `value` input to the property at `pointer` and
+/// activates `out`, or `err` if the write failed or the runtime's
+/// [`PointerPolicy`] does not allow it. Every `{parameter}` of the
+/// pointer becomes an int input value socket.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PointerSetNode {
+    node_id: NodeId,
+    pointer: PointerTemplate,
+}
+
+impl PointerSetNode {
+    /// Fails if the pointer addresses a read-only property.
+    pub fn new(node_id: NodeId, pointer: PointerTemplate) -> Result<Self, PointerError> {
+        if pointer.read_only() {
+            return Err(PointerError::ReadOnly(pointer.as_str().to_string()));
+        }
+        Ok(Self {
+            node_id,
+            pointer,
+        })
+    }
+
+    /// The type of the `value` input, taken from the pointer's property.
+    pub fn value_type(&self) -> ValueType {
+        self.pointer.value_type()
+    }
+
+    fn write(&self, ctx: &mut ExecutionContext) -> Option<()> {
+        let pointer = resolve_inputs(ctx, self.node_id, &self.pointer)?;
+        let value = self.new_value(ctx)?;
+        if value.value_type() != self.value_type() {
+            return None;
+        }
+        ctx.write_pointer(&pointer, value)
+    }
+}
+
+node! {
+    PointerSetNode {
+        op: "pointer/set",
+        kind: Flow,
+        description: "Writes `value` to the property at a pointer.",
+        input_values: { new_value = "value": Value },
+        input_flows: ["in"],
+        output_flows: ["out", "err"],
+        configuration: |node| { "pointer": String(node.pointer.as_str().to_string()) },
+        new_node: |node_id, configuration| Self::new(node_id, configured_pointer(configuration)?)
+            .map_err(|a| ConfigurationError::invalid("pointer", a))?,
+        layout: |node, descriptor| with_parameters(descriptor, &node.pointer),
+    }
+    impl NodeBehavior {
+        fn value(&mut self, _: &mut ExecutionContext) -> Option<()> {
+            panic!("cannot request value from this node");
+        }
+
+        fn activate_input_node(&mut self, ctx: &mut ExecutionContext) -> Option<()> {
+            match self.write(ctx) {
+                Some(()) => ctx.fire(self.node_id, "out"),
+                None => ctx.fire(self.node_id, "err"),
+            }
+        }
+    }
+}
+
+#[test]
+fn test_pointer_templates() {
+    let parse = |pointer: &str| PointerTemplate::parse(pointer);
+    assert_eq!(parse("/nodes/{nodeIndex}/weights/{weight}").unwrap().parameters(), vec!["nodeIndex", "weight"]);
+    assert_eq!(parse("/nodes/{nodeIndex}/rotation").unwrap().value_type(), ValueType::Float4);
+    assert_eq!(parse("/nodes/2/weights/1").unwrap().value_type(), ValueType::Float);
+    assert!(parse("/nodes/{nodeIndex}/matrix").unwrap().read_only());
+    assert!(parse("/nodes/{nodeIndex}/rotation").unwrap().quaternion());
+    assert!(!parse("/materials/{m}/pbrMetallicRoughness/baseColorFactor").unwrap().quaternion());
+    assert_eq!(parse("/nodes/two/rotation"), Err(PointerError::UnknownProperty("/nodes/two/rotation".to_string())));
+    assert_eq!(parse("/nodes/{nodeIndex}/children"), Err(PointerError::UnknownProperty("/nodes/{nodeIndex}/children".to_string())));
+    assert_eq!(parse("nodes/0/scale"), Err(PointerError::NotAbsolute("nodes/0/scale".to_string())));
+    assert!(matches!(parse("/nodes/{}/scale"), Err(PointerError::InvalidSegment { .. })));
+    assert!(matches!(parse("/nodes/{a}/weights/{a}"), Err(PointerError::DuplicateParameter { .. })));
+    let alpha_cutoff = parse("/materials/{m}/alphaCutoff").unwrap();
+    assert_eq!(alpha_cutoff.resolve(|_| Some(4)).as_deref(), Some("/materials/4/alphaCutoff"));
+    assert_eq!(alpha_cutoff.resolve(|_| Some(-1)), None);
+    assert!(PointerSetNode::new(0, parse("/nodes/0/matrix").unwrap()).is_err());
+    assert_eq!(serde_json::to_string(&alpha_cutoff).unwrap(), r#""/materials/{m}/alphaCutoff""#);
+    assert!(serde_json::from_str::<PointerTemplate>(r#""/materials/{m}/alpha""#).is_err());
+}
+
+#[test]
+fn test_pointer_get_and_set() {
+    use std::collections::HashMap;
+    use crate::{Graph, NodeBehavior, NodeBehaviors, OnStartNode, PrintNode, Runtime, VariableDeclaration, VariableGetNode, World};
+
+    struct TestWorld(HashMap<String, Value>);
+
+    impl World for TestWorld {
+        fn read_pointer(&self, pointer: &str) -> Option<Value> {
+            self.0.get(pointer).cloned()
+        }
+
+        fn write_pointer(&mut self, pointer: &str, value: Value) -> Option<()> {
+            *self.0.get_mut(pointer)? = value;
+            Some(())
+        }
+    }
+
+    let mut graph = Graph::new();
+    let mut node_behaviors = NodeBehaviors(HashMap::new());
+
+    let start_id = 0;
+    let set_id = 1;
+    let print_id = 2;
+    let get_id = 3;
+    let node_index_id = 4;
+    let translation_id = 5;
+
+    let start = OnStartNode::new(start_id);
+    let translation_pointer = PointerTemplate::parse("/nodes/{nodeIndex}/translation").unwrap();
+    let set = PointerSetNode::new(set_id, translation_pointer.clone()).unwrap();
+    let print = PrintNode::new(print_id);
+    let get = PointerGetNode::new(get_id, translation_pointer);
+    let node_index = VariableGetNode::new(node_index_id, 0);
+    let translation = VariableGetNode::new(translation_id, 1);
+    assert_eq!(get.value_type(), ValueType::Float3);
+
+    graph
+        .add_node(start.node())
+        .add_node(set.node())
+        .add_node(print.node())
+        .add_node(get.node())
+        .add_node(node_index.node())
+        .add_node(translation.node())
+        .link_flow(start_id, "out", set_id, "in")
+        .link_flow(set_id, "out", print_id, "print_input")
+        .link_value(node_index_id, "value", set_id, "nodeIndex")
+        .link_value(translation_id, "value", set_id, "value")
+        .link_value(get_id, "value", print_id, "print_value")
+        .link_value(node_index_id, "value", get_id, "nodeIndex");
+
+    node_behaviors.add(Box::new(start));
+    node_behaviors.add(Box::new(set));
+    node_behaviors.add(Box::new(print));
+    node_behaviors.add(Box::new(get));
+    node_behaviors.add(Box::new(node_index));
+    node_behaviors.add(Box::new(translation));
+
+    let world = TestWorld(HashMap::from([("/nodes/1/translation".to_string(), Value::Float3([0.0; 3]))]));
+    let mut runtime = Runtime::with_world(graph.resolve().unwrap(), node_behaviors, world);
+    runtime.add_variable(VariableDeclaration {
+        name: "nodeIndex".to_string(),
+        value_type: ValueType::Int,
+        value: Value::Int(1),
+    }).unwrap();
+    runtime.add_variable(VariableDeclaration {
+        name: "translation".to_string(),
+        value_type: ValueType::Float3,
+        value: Value::Float3([1.0, 2.0, 3.0]),
+    }).unwrap();
+    runtime.start();
+
+    assert_eq!(runtime.world().read_pointer("/nodes/1/translation"), Some(Value::Float3([1.0, 2.0, 3.0])));
+    assert_eq!(runtime.existing_values().get_value(get_id, "value"), Some(&Value::Float3([1.0, 2.0, 3.0])));
+    assert_eq!(runtime.existing_values().get_value(get_id, "isValid"), Some(&Value::Bool(true)));
+}
+
+#[test]
+fn test_pointer_policy() {
+    use std::collections::HashMap;
+    use crate::{CollectingLogSink, GltfWorld, Graph, LogNode, NodeBehavior, NodeBehaviors, OnStartNode, Runtime, Severity, VariableDeclaration, VariableGetNode, World};
+
+    let policy = PointerPolicy::deny_all()
+        .allow_read("/materials/{}/alphaCutoff")
+        .allow_write("/nodes/{}/translation")
+        .allow_write("/nodes/{}/matrix");
+    assert_eq!(policy.check_read("/materials/2/alphaCutoff"), Ok(()));
+    assert_eq!(policy.check_write("/materials/2/alphaCutoff"), Err(PointerError::WriteBlocked("/materials/2/alphaCutoff".to_string())));
+    assert_eq!(policy.check_read("/nodes/0/translation"), Ok(()));
+    assert_eq!(policy.check_write("/nodes/0/translation"), Ok(()));
+    assert_eq!(policy.check_read("/nodes/zero/translation"), Err(PointerError::ReadBlocked("/nodes/zero/translation".to_string())));
+    assert_eq!(policy.check_write("/nodes/0/matrix"), Err(PointerError::ReadOnly("/nodes/0/matrix".to_string())));
+    assert_eq!(PointerPolicy::allow_all().check_write("/nodes/0/matrix"), Err(PointerError::ReadOnly("/nodes/0/matrix".to_string())));
+
+    let mut graph = Graph::new();
+    let mut node_behaviors = NodeBehaviors(HashMap::new());
+
+    let start_id = 0;
+    let set_id = 1;
+    let log_id = 2;
+    let scale_id = 3;
+
+    let start = OnStartNode::new(start_id);
+    let set = PointerSetNode::new(set_id, PointerTemplate::parse("/nodes/0/scale").unwrap()).unwrap();
+    let log = LogNode::new(log_id, "blocked", Severity::Error);
+    let scale = VariableGetNode::new(scale_id, 0);
+
+    graph
+        .add_node(start.node())
+        .add_node(set.node())
+        .add_node(log.node())
+        .add_node(scale.node())
+        .link_flow(start_id, "out", set_id, "in")
+        .link_flow(set_id, "err", log_id, "in")
+        .link_value(scale_id, "value", set_id, "value");
+
+    node_behaviors.add(Box::new(start));
+    node_behaviors.add(Box::new(set));
+    node_behaviors.add(Box::new(log));
+    node_behaviors.add(Box::new(scale));
+
+    let world = GltfWorld::from_json(r#"{ "nodes": [{}] }"#).unwrap();
+    let sink = CollectingLogSink::default();
+    let mut runtime = Runtime::with_world(graph.resolve().unwrap(), node_behaviors, world);
+    runtime.set_log_sink(sink.clone());
+    runtime.set_pointer_policy(policy);
+    runtime.add_variable(VariableDeclaration {
+        name: "scale".to_string(),
+        value_type: ValueType::Float3,
+        value: Value::Float3([2.0; 3]),
+    }).unwrap();
+    runtime.start();
+
+    assert_eq!(runtime.world().read_pointer("/nodes/0/scale"), Some(Value::Float3([1.0; 3])));
+    assert_eq!(sink.messages(), vec!["writing pointer `/nodes/0/scale` is not allowed", "blocked"]);
+}
@@ -0,0 +1,275 @@
+use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use crate::{Completion, CustomEvent, LogEntry, LogSink, Runtime, Severity, Value, World, WorldEvent};
+
+/// Something the host did to a [`Runtime`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum HostInput {
+    Start,
+    /// A tick of `dt` seconds, along with the events the world raised for it.
+    Tick {
+        dt: f32,
+        world_events: Vec<WorldEvent>,
+    },
+    SendEvent {
+        event: usize,
+        values: HashMap<String, Value>,
+    },
+    SetVariable {
+        index: usize,
+        value: Value,
+    },
+    Select {
+        node: usize,
+        controller: i32,
+        point: [f32; 3],
+        ray_origin: [f32; 3],
+    },
+    Hover {
+        node: Option<usize>,
+        controller: i32,
+    },
+    Complete(Completion),
+    /// The host changed the scene through [`Runtime::write_pointer`].
+    WritePointer {
+        pointer: String,
+        value: Value,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PointerWrite {
+    pub pointer: String,
+    pub value: Value,
+}
+
+/// What the graph did in response to one host input.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Outputs {
+    pub logs: Vec<LogEntry>,
+    /// Custom events sent by the graph, in the order they reached the subscribers.
+    pub events: Vec<CustomEvent>,
+    /// The pointers the graph and its interpolations wrote to the world.
+    pub pointer_writes: Vec<PointerWrite>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedInput {
+    pub input: HostInput,
+    pub outputs: Outputs,
+}
+
+/// Every host input a runtime received while recording, each with the outputs
+/// it caused. Declaring variables and events is part of building the runtime
+/// and is not recorded.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Recording {
+    pub inputs: Vec<RecordedInput>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ReplayError {
+    #[error("recording is not valid JSON: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("replay diverged at input {index} ({input:?})")]
+    Diverged {
+        index: usize,
+        input: HostInput,
+        expected: Box<Outputs>,
+        found: Box<Outputs>,
+    },
+}
+
+impl Recording {
+    pub fn from_json(json: &str) -> Result<Self, ReplayError> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+
+    /// Feeds every recorded input to `runtime` and checks that it logs, sends
+    /// and writes exactly what it did when recorded, stopping at the first
+    /// input where it does not. `runtime` has to be built like the recorded one,
+    /// with the same graph, declarations and initial world, and not be started.
+    pub fn replay<W: World>(&self, runtime: &mut Runtime<W>) -> Result<(), ReplayError> {
+        runtime.start_recording();
+        for (index, recorded) in self.inputs.iter().enumerate() {
+            runtime.apply(recorded.input.clone());
+            let found = runtime.recording()
+                .and_then(|a| a.inputs.last())
+                .map(|a| a.outputs.clone())
+                .unwrap_or_default();
+            if found != recorded.outputs {
+                runtime.stop_recording();
+                return Err(ReplayError::Diverged {
+                    index,
+                    input: recorded.input.clone(),
+                    expected: Box::new(recorded.outputs.clone()),
+                    found: Box::new(found),
+                });
+            }
+        }
+        runtime.stop_recording();
+        Ok(())
+    }
+}
+
+/// Passes everything on to `log` while keeping a copy in `logs`.
+pub(crate) struct RecordingLogSink<'a> {
+    pub log: &'a mut dyn LogSink,
+    pub logs: &'a mut Vec<LogEntry>,
+}
+
+impl LogSink for RecordingLogSink<'_> {
+    fn log(&mut self, severity: Severity, message: &str) {
+        self.logs.push(LogEntry {
+            severity,
+            message: message.to_string(),
+        });
+        self.log.log(severity, message);
+    }
+}
+
+/// Passes everything on to `world` while keeping the successful pointer
+/// writes in `writes`.
+pub(crate) struct RecordingWorld<'a> {
+    pub world: &'a mut dyn World,
+    pub writes: &'a mut Vec<PointerWrite>,
+}
+
+impl World for RecordingWorld<'_> {
+    fn read_pointer(&self, pointer: &str) -> Option<Value> {
+        self.world.read_pointer(pointer)
+    }
+
+    fn write_pointer(&mut self, pointer: &str, value: Value) -> Option<()> {
+        self.world.write_pointer(pointer, value.clone())?;
+        self.writes.push(PointerWrite {
+            pointer: pointer.to_string(),
+            value,
+        });
+        Some(())
+    }
+
+    fn start_animation(&mut self, animation: i32, start_time: f32, end_time: f32, speed: f32) -> Option<()> {
+        self.world.start_animation(animation, start_time, end_time, speed)
+    }
+
+    fn stop_animation(&mut self, animation: i32) -> Option<()> {
+        self.world.stop_animation(animation)
+    }
+
+    fn stop_animation_at(&mut self, animation: i32, stop_time: f32) -> Option<()> {
+        self.world.stop_animation_at(animation, stop_time)
+    }
+
+    fn parent(&self, node: usize) -> Option<usize> {
+        self.world.parent(node)
+    }
+
+    fn selectable(&self, node: usize) -> bool {
+        self.world.selectable(node)
+    }
+
+    fn hoverable(&self, node: usize) -> bool {
+        self.world.hoverable(node)
+    }
+
+    fn visible(&self, node: usize) -> bool {
+        self.world.visible(node)
+    }
+
+    fn advance(&mut self, dt: f32) {
+        self.world.advance(dt)
+    }
+
+    fn take_events(&mut self) -> Vec<WorldEvent> {
+        self.world.take_events()
+    }
+
+    fn delta_time(&mut self) -> f32 {
+        self.world.delta_time()
+    }
+}
+
+#[test]
+fn test_record_and_replay() {
+    use crate::{CollectingLogSink, GltfWorld, Graph, LogNode, NodeBehavior, NodeBehaviors, OnTickNode, PointerGetNode, PointerSetNode, PointerTemplate, ValueType, VariableDeclaration, VariableGetNode};
+
+    // Every tick copies the `offset` variable to the translation of node 0 and
+    // logs the translation of node 1.
+    let runtime = |message: &str| {
+        let mut graph = Graph::new();
+        let mut node_behaviors = NodeBehaviors(HashMap::new());
+
+        let tick_id = 0;
+        let set_id = 1;
+        let offset_id = 2;
+        let log_id = 3;
+        let get_id = 4;
+
+        let tick = OnTickNode::new(tick_id);
+        let set = PointerSetNode::new(set_id, PointerTemplate::parse("/nodes/0/translation").unwrap()).unwrap();
+        let offset = VariableGetNode::new(offset_id, 0);
+        let log = LogNode::new(log_id, message, Severity::Info);
+        let get = PointerGetNode::new(get_id, PointerTemplate::parse("/nodes/1/translation").unwrap());
+
+        graph
+            .add_node(tick.node())
+            .add_node(set.node())
+            .add_node(offset.node())
+            .add_node(log.node())
+            .add_node(get.node())
+            .link_flow(tick_id, "out", set_id, "in")
+            .link_flow(set_id, "out", log_id, "in")
+            .link_value(offset_id, "value", set_id, "value")
+            .link_value(get_id, "value", log_id, "value");
+
+        node_behaviors.add(Box::new(tick));
+        node_behaviors.add(Box::new(set));
+        node_behaviors.add(Box::new(offset));
+        node_behaviors.add(Box::new(log));
+        node_behaviors.add(Box::new(get));
+
+        let world = GltfWorld::from_json(r#"{ "nodes": [{}, {}] }"#).unwrap();
+        let mut runtime = Runtime::with_world(graph.resolve().unwrap(), node_behaviors, world);
+        runtime.set_log_sink(CollectingLogSink::default());
+        runtime.add_variable(VariableDeclaration {
+            name: "offset".to_string(),
+            value_type: ValueType::Float3,
+            value: Value::Float3([1.0, 0.0, 0.0]),
+        }).unwrap();
+        runtime
+    };
+
+    let mut recorded = runtime("node 1 is at {value}");
+    recorded.start_recording();
+    recorded.start();
+    recorded.tick(0.5);
+    recorded.set_variable(0, Value::Float3([2.0, 0.0, 0.0])).unwrap();
+    recorded.write_pointer("/nodes/1/translation", Value::Float3([0.0, 3.0, 0.0])).unwrap();
+    recorded.tick(0.5);
+    let recording = recorded.stop_recording().unwrap();
+
+    assert_eq!(recording.inputs.len(), 5);
+    assert_eq!(recording.inputs[4].outputs, Outputs {
+        logs: vec![LogEntry {
+            severity: Severity::Info,
+            message: "node 1 is at [0, 3, 0]".to_string(),
+        }],
+        events: vec![],
+        pointer_writes: vec![PointerWrite {
+            pointer: "/nodes/0/translation".to_string(),
+            value: Value::Float3([2.0, 0.0, 0.0]),
+        }],
+    });
+
+    let recording = Recording::from_json(&recording.to_json()).unwrap();
+    recording.replay(&mut runtime("node 1 is at {value}")).unwrap();
+    let Err(ReplayError::Diverged { index, .. }) = recording.replay(&mut runtime("node 1 is now at {value}")) else {
+        panic!("replaying a different graph should diverge");
+    };
+    assert_eq!(index, 1);
+}
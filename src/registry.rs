@@ -0,0 +1,443 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+use crate::{AnimationStartNode, Configuration, ConfigurationError, Declarations, ExecutableGraph, Graph, GraphError, Node, AnimationStopAtNode, AnimationStopNode, EventReceiveNode, EventSendNode, LogNode, MathAdd, MathPi, NodeArchetypeBuilder, NodeBehavior, NodeBehaviors, NodeDescriptor, NodeId, OnHoverInNode, OnHoverOutNode, OnSelectNode, OnStartNode, OnTickNode, PointerGetNode, PointerInterpolateNode, PointerSetNode, PrintNode, SequenceNode, ValueType, VariableError, VariableGetNode, VariableInterpolateNode, VariableSetMultipleNode, VariableSetNode};
+
+/// Creates the nodes of a registered type. Factories may capture what the
+/// nodes need from the host, such as a handle to its audio system.
+pub type NewNode = Arc<dyn Fn(NodeId, &Configuration, &Declarations) -> Result<Box<dyn NodeBehavior>, ConfigurationError> + Send + Sync>;
+
+#[derive(Debug, thiserror::Error)]
+pub enum LoadError {
+    #[error("node {node_id} uses `{op}`, which is not registered")]
+    UnknownOp { node_id: NodeId, op: String },
+    #[error("node {node_id} has a socket `{socket}` its type does not declare")]
+    UnknownSocket { node_id: NodeId, socket: String },
+    #[error("node {node_id} lacks the socket `{socket}` its type declares")]
+    MissingSocket { node_id: NodeId, socket: String },
+    #[error("input value socket `{socket}` of node {node_id} takes {expected:?}, not {found:?}")]
+    TypeMismatch { node_id: NodeId, socket: String, expected: ValueType, found: ValueType },
+    #[error("node {node_id} is not configured correctly: {source}")]
+    Configuration { node_id: NodeId, source: ConfigurationError },
+    #[error(transparent)]
+    Graph(#[from] GraphError),
+    #[error(transparent)]
+    Variable(#[from] VariableError),
+}
+
+/// The node types a graph may use, by op name. Registries are plain values, so
+/// a host can keep several, e.g. a restricted one for user content, and hand
+/// each to the loader it applies to.
+#[derive(Clone, Default)]
+pub struct NodeRegistry {
+    nodes: HashMap<String, (NodeDescriptor, NewNode)>,
+}
+
+impl NodeRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Every node type this crate implements.
+    pub fn builtin() -> Self {
+        let mut registry = Self::new();
+        registry
+            .register::<MathPi>()
+            .register::<MathAdd>()
+            .register::<PrintNode>()
+            .register::<SequenceNode>()
+            .register::<OnStartNode>()
+            .register::<OnTickNode>()
+            .register::<EventSendNode>()
+            .register::<EventReceiveNode>()
+            .register::<VariableGetNode>()
+            .register::<VariableSetNode>()
+            .register::<VariableSetMultipleNode>()
+            .register::<VariableInterpolateNode>()
+            .register::<PointerGetNode>()
+            .register::<PointerSetNode>()
+            .register::<PointerInterpolateNode>()
+            .register::<AnimationStartNode>()
+            .register::<AnimationStopNode>()
+            .register::<AnimationStopAtNode>()
+            .register::<LogNode>()
+            .register::<OnSelectNode>()
+            .register::<OnHoverInNode>()
+            .register::<OnHoverOutNode>();
+        registry
+    }
+
+    /// A copy of the process-wide default registry that [`crate::RegisterNode`]
+    /// adds to.
+    pub fn global() -> Self {
+        get_registry().lock().unwrap().clone()
+    }
+
+    pub fn register<T: NodeArchetypeBuilder + 'static>(&mut self) -> &mut Self {
+        self.nodes.insert(T::name(), (T::descriptor(), Arc::new(T::new_node)));
+        self
+    }
+
+    /// Adds a node type that is only known at runtime: `descriptor` says what
+    /// it is and `new_node`, a closure or boxed factory, creates its nodes.
+    /// Configurations are checked against `descriptor` before they reach it.
+    pub fn register_with(
+        &mut self,
+        descriptor: NodeDescriptor,
+        new_node: impl Fn(NodeId, &Configuration, &Declarations) -> Result<Box<dyn NodeBehavior>, ConfigurationError> + Send + Sync + 'static,
+    ) -> &mut Self {
+        let checked = descriptor.clone();
+        let new_node: NewNode = Arc::new(move |node_id, configuration, declarations| {
+            configuration.check(&checked)?;
+            new_node(node_id, configuration, declarations)
+        });
+        self.nodes.insert(descriptor.op.clone(), (descriptor, new_node));
+        self
+    }
+
+    /// Keeps only the node types whose op `f` accepts.
+    pub fn retain(&mut self, mut f: impl FnMut(&str) -> bool) -> &mut Self {
+        self.nodes.retain(|op, _| f(op));
+        self
+    }
+
+    pub fn contains(&self, op: &str) -> bool {
+        self.nodes.contains_key(op)
+    }
+
+    /// The registered op names, sorted.
+    pub fn ops(&self) -> Vec<&str> {
+        let mut ops = self.nodes.keys().map(|a| a.as_str()).collect::<Vec<_>>();
+        ops.sort();
+        ops
+    }
+
+    pub fn descriptor(&self, op: &str) -> Option<&NodeDescriptor> {
+        Some(&self.nodes.get(op)?.0)
+    }
+
+    /// The registered node types, sorted by op.
+    pub fn descriptors(&self) -> Vec<&NodeDescriptor> {
+        let mut descriptors = self.nodes.values().map(|(a, _)| a).collect::<Vec<_>>();
+        descriptors.sort_by(|a, b| a.op.cmp(&b.op));
+        descriptors
+    }
+
+    /// A new node of type `op` as it appears in a graph with `declarations`,
+    /// laid out for `configuration`.
+    pub fn build(&self, op: &str, node_id: NodeId, configuration: &Configuration, declarations: &Declarations) -> Result<Node, LoadError> {
+        Ok(self.new_node(op, node_id, configuration, declarations)?.node())
+    }
+
+    pub fn new_node(&self, op: &str, node_id: NodeId, configuration: &Configuration, declarations: &Declarations) -> Result<Box<dyn NodeBehavior>, LoadError> {
+        let (_, new_node) = self.nodes.get(op).ok_or_else(|| LoadError::UnknownOp {
+            node_id,
+            op: op.to_string(),
+        })?;
+        new_node(node_id, configuration, declarations).map_err(|source| LoadError::Configuration { node_id, source })
+    }
+
+    /// Checks the nodes of an edited graph against their types: every op must
+    /// be registered, every configuration valid, every socket one the node has
+    /// in the layout of its configuration, and linked input values must have
+    /// the declared type where both ends declare one.
+    pub fn validate(&self, graph: &Graph) -> Result<(), LoadError> {
+        self.check(graph, &self.new_nodes(graph)?)
+    }
+
+    /// Turns an edited graph into what a [`crate::Runtime`] runs, once it passes
+    /// [`NodeRegistry::validate`] and resolves.
+    pub fn load(&self, graph: &Graph) -> Result<(ExecutableGraph, NodeBehaviors), LoadError> {
+        let node_behaviors = self.new_nodes(graph)?;
+        self.check(graph, &node_behaviors)?;
+        Ok((graph.resolve()?, node_behaviors))
+    }
+
+    fn new_nodes(&self, graph: &Graph) -> Result<NodeBehaviors, LoadError> {
+        let mut node_behaviors = NodeBehaviors(HashMap::new());
+        for node in &graph.nodes {
+            node_behaviors.add(self.new_node(&node.op, node.node_id, &node.configuration, &graph.declarations)?);
+        }
+        Ok(node_behaviors)
+    }
+
+    fn check(&self, graph: &Graph, node_behaviors: &NodeBehaviors) -> Result<(), LoadError> {
+        let layouts = node_behaviors.0.iter().map(|(node_id, behavior)| (*node_id, behavior.layout())).collect::<HashMap<_, _>>();
+        for node in &graph.nodes {
+            let layout = &layouts[&node.node_id];
+            let unknown_socket = |socket: &str| LoadError::UnknownSocket {
+                node_id: node.node_id,
+                socket: socket.to_string(),
+            };
+            if let Some(socket) = node.input_value_sockets.iter().find(|a| layout.input_value_socket(a).is_none()) {
+                return Err(unknown_socket(socket));
+            }
+            if let Some(socket) = node.output_value_sockets.iter().find(|a| layout.output_value_socket(a).is_none()) {
+                return Err(unknown_socket(socket));
+            }
+            if let Some(socket) = node.input_flow_sockets.iter().find(|a| !layout.input_flow_sockets.contains(a)) {
+                return Err(unknown_socket(socket));
+            }
+            if let Some(socket) = node.output_flow_sockets.iter().find(|a| !layout.output_flow_sockets.contains(a)) {
+                return Err(unknown_socket(socket));
+            }
+            let missing_socket = |socket: &str| LoadError::MissingSocket {
+                node_id: node.node_id,
+                socket: socket.to_string(),
+            };
+            if let Some(socket) = layout.input_value_sockets.iter().find(|a| !node.input_value_sockets.contains(&a.name)) {
+                return Err(missing_socket(&socket.name));
+            }
+            if let Some(socket) = layout.output_value_sockets.iter().find(|a| !node.output_value_sockets.contains(&a.name)) {
+                return Err(missing_socket(&socket.name));
+            }
+            if let Some(socket) = layout.input_flow_sockets.iter().find(|a| !node.input_flow_sockets.contains(a)) {
+                return Err(missing_socket(socket));
+            }
+            if let Some(socket) = layout.output_flow_sockets.iter().find(|a| !node.output_flow_sockets.contains(a)) {
+                return Err(missing_socket(socket));
+            }
+            for (socket, value) in &node.values {
+                let expected = layout.input_value_socket(socket).and_then(|a| a.value_type);
+                if let Some(expected) = expected.filter(|a| *a != value.value_type()) {
+                    return Err(LoadError::TypeMismatch {
+                        node_id: node.node_id,
+                        socket: socket.clone(),
+                        expected,
+                        found: value.value_type(),
+                    });
+                }
+            }
+        }
+        for link in &graph.value_links {
+            let expected = layouts.get(&link.input.node_id)
+                .and_then(|a| a.input_value_socket(&link.input.name))
+                .and_then(|a| a.value_type);
+            let found = layouts.get(&link.output.node_id)
+                .and_then(|a| a.output_value_socket(&link.output.name))
+                .and_then(|a| a.value_type);
+            if let (Some(expected), Some(found)) = (expected, found) {
+                if expected != found {
+                    return Err(LoadError::TypeMismatch {
+                        node_id: link.input.node_id,
+                        socket: link.input.name.clone(),
+                        expected,
+                        found,
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+static REGISTRY: OnceLock<Arc<Mutex<NodeRegistry>>> = OnceLock::new();
+
+/// The process-wide default registry. Prefer passing a [`NodeRegistry`] around.
+pub fn get_registry() -> Arc<Mutex<NodeRegistry>> {
+    REGISTRY.get_or_init(|| {
+        Arc::new(Mutex::new(NodeRegistry::default()))
+    }).clone()
+}
+
+pub trait RegisterNode {
+    /// Adds the node type to the default registry.
+    fn register();
+}
+
+impl<T: NodeArchetypeBuilder + NodeBehavior + 'static> RegisterNode for T {
+    fn register() {
+        get_registry()
+            .lock().unwrap()
+            .register::<T>();
+    }
+}
+
+/// A node type known only at runtime, whose nodes share state with the host:
+/// a studio's own audio node, registered under its vendor prefix.
+#[cfg(test)]
+pub(crate) struct PlayNode {
+    node_id: NodeId,
+    clip: i32,
+    played: Arc<Mutex<Vec<(i32, crate::Value)>>>,
+}
+
+#[cfg(test)]
+impl PlayNode {
+    /// Registers `ACME_audio/play` with `registry`, returning what its nodes play.
+    pub(crate) fn register_with(registry: &mut NodeRegistry) -> Arc<Mutex<Vec<(i32, crate::Value)>>> {
+        let played = Arc::new(Mutex::new(vec![]));
+        let host = played.clone();
+        let descriptor = PlayNode {
+            node_id: 0,
+            clip: 0,
+            played: played.clone(),
+        }.layout();
+        registry.register_with(descriptor, move |node_id, configuration, _| {
+            Ok(Box::new(PlayNode {
+                node_id,
+                clip: configuration.int("clip")?.unwrap_or(0),
+                played: host.clone(),
+            }))
+        });
+        played
+    }
+}
+
+#[cfg(test)]
+impl NodeBehavior for PlayNode {
+    fn node_id(&self) -> NodeId {
+        self.node_id
+    }
+
+    fn layout(&self) -> NodeDescriptor {
+        NodeDescriptor::new("ACME_audio/play", crate::NodeKind::Flow, "Plays an audio clip.")
+            .input_flow("in")
+            .input_value("volume", ValueType::Float)
+            .output_flow("out")
+            .configuration("clip", crate::ConfigurationType::Int)
+    }
+
+    fn configuration(&self) -> Configuration {
+        Configuration::new().with("clip", self.clip)
+    }
+
+    fn value(&mut self, _: &mut crate::ExecutionContext) -> Option<()> {
+        Some(())
+    }
+
+    fn activate_input_node(&mut self, ctx: &mut crate::ExecutionContext) -> Option<()> {
+        let volume = ctx.input_value(self.node_id, "volume")?;
+        self.played.lock().unwrap().push((self.clip, volume));
+        ctx.fire(self.node_id, "out")
+    }
+}
+
+#[test]
+fn test_registry() {
+    use crate::{CollectingLogSink, Runtime};
+
+    let registry = NodeRegistry::builtin();
+    let declarations = Declarations::new();
+    assert!(registry.contains("debug/log"));
+    assert_eq!(registry.ops().len(), 22);
+
+    let mut graph = Graph::new();
+    graph
+        .add_node(registry.build("event/onStart", 0, &Configuration::new(), &declarations).unwrap())
+        .add_node(registry.build("custom/print", 1, &Configuration::new(), &declarations).unwrap())
+        .add_node(registry.build("math/pi", 2, &Configuration::new(), &declarations).unwrap())
+        .link_flow(0, "out", 1, "print_input")
+        .link_value(2, "value", 1, "print_value");
+
+    let sink = CollectingLogSink::default();
+    let mut runtime = Runtime::load(&registry, &graph).unwrap();
+    runtime.set_log_sink(sink.clone());
+    runtime.start();
+    assert_eq!(sink.entries().len(), 1);
+
+    // A restricted copy leaves the original alone.
+    let mut sandboxed = registry.clone();
+    sandboxed.retain(|op| op != "custom/print");
+    assert!(registry.contains("custom/print"));
+    assert!(matches!(sandboxed.load(&graph), Err(LoadError::UnknownOp { node_id: 1, .. })));
+
+    // Other tests may register with the default registry too.
+    MathPi::register();
+    assert!(NodeRegistry::global().contains("math/pi"));
+}
+
+#[test]
+fn test_registry_descriptors() {
+    use crate::NodeKind;
+
+    let registry = NodeRegistry::builtin();
+    let declarations = Declarations::new();
+    let mut add = Graph::new();
+    add.add_node(registry.build("math/add", 3, &Configuration::new(), &declarations).unwrap());
+    assert!(matches!(registry.load(&add), Err(LoadError::Graph(GraphError::UnlinkedInput { node_id: 3, .. }))));
+
+    let descriptor = registry.descriptor("math/add").unwrap();
+    assert_eq!(descriptor.category, "math");
+    assert_eq!(descriptor.kind, NodeKind::Pure);
+    assert_eq!(descriptor.input_value_socket("a").unwrap().value_type, Some(ValueType::Float));
+    assert_eq!(registry.descriptors().first().unwrap().op, "animation/start");
+
+    add
+        .add_node(registry.build("event/onSelect", 4, &Configuration::new(), &declarations).unwrap())
+        .add_node(registry.build("math/pi", 2, &Configuration::new(), &declarations).unwrap())
+        .link_value(4, "selectedNodeIndex", 3, "a")
+        .link_value(2, "value", 3, "b");
+    assert!(matches!(
+        registry.load(&add),
+        Err(LoadError::TypeMismatch { node_id: 3, expected: ValueType::Float, found: ValueType::Int, .. })
+    ));
+    add.nodes[0].output_value_sockets[0] = "sum".to_string();
+    assert!(matches!(registry.validate(&add), Err(LoadError::UnknownSocket { node_id: 3, .. })));
+    add.nodes[0].output_value_sockets.clear();
+    assert!(matches!(registry.validate(&add), Err(LoadError::MissingSocket { node_id: 3, socket }) if socket == "value"));
+}
+
+#[test]
+fn test_registry_configuration() {
+    use crate::{CollectingLogSink, Runtime};
+
+    // The configuration goes through serialization and the loader, and decides
+    // the sockets of the node.
+    let registry = NodeRegistry::builtin();
+    let declarations = Declarations::new();
+    let mut graph = Graph::new();
+    graph
+        .add_node(registry.build("event/onStart", 0, &Configuration::new(), &declarations).unwrap())
+        .add_node(registry.build("debug/log", 1, &Configuration::new().with("message", "pi is {pi}"), &declarations).unwrap())
+        .add_node(registry.build("math/pi", 2, &Configuration::new(), &declarations).unwrap())
+        .link_flow(0, "out", 1, "in")
+        .link_value(2, "value", 1, "pi");
+    let mut graph = serde_json::from_str::<Graph>(&serde_json::to_string(&graph).unwrap()).unwrap();
+    let sink = CollectingLogSink::default();
+    let mut runtime = Runtime::load(&registry, &graph).unwrap();
+    runtime.set_log_sink(sink.clone());
+    runtime.start();
+    assert_eq!(sink.messages(), vec![format!("pi is {}", std::f32::consts::PI)]);
+    graph.nodes[1].configuration.set("severity", "high");
+    assert!(matches!(registry.load(&graph), Err(LoadError::Configuration { node_id: 1, .. })));
+
+    // The type of a pointer's value is only known once the pointer is configured.
+    assert_eq!(registry.descriptor("pointer/get").unwrap().output_value_socket("value").unwrap().value_type, None);
+    let get = registry.new_node("pointer/get", 0, &Configuration::new(), &declarations).unwrap();
+    assert_eq!(get.layout().output_value_socket("value").unwrap().value_type, Some(ValueType::Float3));
+    let mut set = Graph::new();
+    set
+        .add_node(registry.build("pointer/set", 0, &Configuration::new(), &declarations).unwrap())
+        .add_node(registry.build("math/pi", 1, &Configuration::new(), &declarations).unwrap())
+        .add_node(registry.build("event/onSelect", 2, &Configuration::new(), &declarations).unwrap())
+        .link_value(2, "selectedNodeIndex", 0, "nodeIndex")
+        .link_value(1, "value", 0, "value");
+    assert!(matches!(
+        registry.validate(&set),
+        Err(LoadError::TypeMismatch { node_id: 0, expected: ValueType::Float3, found: ValueType::Float, .. })
+    ));
+}
+
+#[test]
+fn test_register_with() {
+    use crate::{Runtime, Value};
+
+    let mut registry = NodeRegistry::builtin();
+    let played = PlayNode::register_with(&mut registry);
+    let declarations = Declarations::new();
+    let mut graph = Graph::new();
+    graph
+        .add_node(registry.build("event/onStart", 0, &Configuration::new(), &declarations).unwrap())
+        .add_node(registry.build("ACME_audio/play", 1, &Configuration::new().with("clip", 7), &declarations).unwrap())
+        .add_node(registry.build("math/pi", 2, &Configuration::new(), &declarations).unwrap())
+        .link_flow(0, "out", 1, "in")
+        .link_value(2, "value", 1, "volume");
+    let mut runtime = Runtime::load(&registry, &graph).unwrap();
+    runtime.start();
+    assert_eq!(*played.lock().unwrap(), vec![(7, Value::Float(std::f32::consts::PI))]);
+    assert!(!NodeRegistry::builtin().contains("ACME_audio/play"));
+    assert!(matches!(
+        registry.build("ACME_audio/play", 1, &Configuration::new().with("volume", 1), &declarations),
+        Err(LoadError::Configuration { source: ConfigurationError::Unknown(_), .. })
+    ));
+}
@@ -0,0 +1,987 @@
+use std::collections::{HashMap, VecDeque};
+use serde::{Deserialize, Serialize};
+use crate::record::{HostInput, Outputs, PointerWrite, Recording, RecordedInput, RecordingLogSink, RecordingWorld};
+use crate::snapshot::{Snapshot, SnapshotError};
+use crate::{Activation, Breakpoint, CustomEvent, EmptyWorld, EventDeclaration, EventError, ExecutableGraph, ExecutionContext, ExistingValues, Graph, Interpolation, InterpolationTarget, LoadError, LogEntry, LogSink, NodeBehaviors, NodeId, NodeRegistry, PointerPolicy, RuntimeEvent, Severity, StdoutLogSink, TraceKind, TraceSink, Tracer, Value, VariableDeclaration, VariableError, Variables, World, WorldEvent};
+
+/// Something outside the graph a deferred flow waits for.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Completion {
+    /// The animation with this index finished playing or reached its stop time.
+    Animation(i32),
+}
+
+/// An output flow socket that activates once its completion is reported.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingFlow {
+    pub node_id: NodeId,
+    pub name: String,
+    pub completion: Completion,
+}
+
+type Subscriber = (usize, Box<dyn FnMut(&CustomEvent)>);
+
+/// A part of a host input still to be carried out. Host inputs are split into
+/// these so that execution can pause between any two activations and continue
+/// later from where it stopped.
+enum Work {
+    /// Attributes the outputs of the work after it to the recorded input at
+    /// this index.
+    Record(usize),
+    Start,
+    Tick {
+        dt: f32,
+        replayed: Option<Vec<WorldEvent>>,
+    },
+    /// Moves time and the world on, then queues the rest of a tick.
+    Advance {
+        dt: f32,
+        replayed: Option<Vec<WorldEvent>>,
+    },
+    Select {
+        node: usize,
+        controller: i32,
+        point: [f32; 3],
+        ray_origin: [f32; 3],
+    },
+    Hover {
+        node: Option<usize>,
+        controller: i32,
+    },
+    Complete(Completion),
+    /// Sets a variable for the host, after the work queued before it.
+    SetVariable {
+        index: usize,
+        value: Value,
+    },
+    /// Writes a pointer for the host, after the work queued before it.
+    WritePointer {
+        pointer: String,
+        value: Value,
+    },
+    /// A custom event raised by the world or sent by the host, logged as an
+    /// error if invalid.
+    QueueEvent {
+        event: usize,
+        values: HashMap<String, Value>,
+    },
+    DispatchPendingEvents,
+    AdvanceInterpolations(f32),
+    Dispatch(RuntimeEvent),
+    HandleEvent {
+        node_id: NodeId,
+        event: RuntimeEvent,
+    },
+    /// Dispatches the events in turn, until a node handling the previous one
+    /// stops the propagation.
+    Propagate {
+        previous: Option<RuntimeEvent>,
+        events: VecDeque<RuntimeEvent>,
+    },
+    Fire {
+        node_id: NodeId,
+        name: String,
+    },
+    Publish,
+}
+
+/// Drives a graph from the host: `start` once, then `tick` every frame.
+pub struct Runtime<W: World = EmptyWorld> {
+    graph: ExecutableGraph,
+    node_behaviors: NodeBehaviors,
+    existing_values: ExistingValues,
+    variables: Variables,
+    /// Node ids in ascending order, so that dispatch order does not depend on
+    /// hash map iteration.
+    node_ids: Vec<NodeId>,
+    events: Vec<EventDeclaration>,
+    /// Custom events waiting to be dispatched on the next tick.
+    pending_events: VecDeque<CustomEvent>,
+    /// Custom events sent by the graph during the current start or tick.
+    sent_events: VecDeque<CustomEvent>,
+    subscribers: Vec<Subscriber>,
+    pending_flows: Vec<PendingFlow>,
+    interpolations: Vec<Interpolation>,
+    /// The node each controller hovers.
+    hovered: HashMap<i32, usize>,
+    started: bool,
+    time_since_start: f32,
+    world: W,
+    pointer_policy: PointerPolicy,
+    log: Box<dyn LogSink>,
+    recording: Option<Recording>,
+    /// The index of the recorded input whose work is being carried out. Input
+    /// received while execution is paused waits behind the work of the one
+    /// before, so this is not always the last one.
+    recorded_input: Option<usize>,
+    tracer: Tracer,
+    /// Host input still to be carried out, the next first.
+    work: VecDeque<Work>,
+    scheduled: Vec<Activation>,
+    breakpoints: Vec<Breakpoint>,
+    /// Whether execution stopped before the last scheduled activation.
+    paused: bool,
+}
+
+impl Runtime {
+    /// A runtime for a graph that does not touch any scene.
+    pub fn new(graph: ExecutableGraph, node_behaviors: NodeBehaviors) -> Self {
+        Self::with_world(graph, node_behaviors, EmptyWorld)
+    }
+
+    /// A runtime for an edited graph, whose node types are looked up in
+    /// `registry`.
+    pub fn load(registry: &NodeRegistry, graph: &Graph) -> Result<Self, LoadError> {
+        Self::load_with_world(registry, graph, EmptyWorld)
+    }
+}
+
+impl<W: World> Runtime<W> {
+    /// Like [`Runtime::load`], in `world`. The variables and events the graph
+    /// declares are added to the runtime.
+    pub fn load_with_world(registry: &NodeRegistry, graph: &Graph, world: W) -> Result<Self, LoadError> {
+        let (executable, node_behaviors) = registry.load(graph)?;
+        let mut runtime = Self::with_world(executable, node_behaviors, world);
+        for variable in &graph.declarations.variables {
+            runtime.add_variable(variable.clone())?;
+        }
+        for event in &graph.declarations.events {
+            runtime.add_event(event.clone());
+        }
+        Ok(runtime)
+    }
+
+    pub fn with_world(graph: ExecutableGraph, node_behaviors: NodeBehaviors, world: W) -> Self {
+        let mut node_ids = node_behaviors.0.keys().copied().collect::<Vec<_>>();
+        node_ids.sort();
+        Self {
+            graph,
+            node_behaviors,
+            existing_values: ExistingValues::default(),
+            variables: Variables::default(),
+            node_ids,
+            events: vec![],
+            pending_events: VecDeque::new(),
+            sent_events: VecDeque::new(),
+            subscribers: vec![],
+            pending_flows: vec![],
+            interpolations: vec![],
+            hovered: HashMap::new(),
+            started: false,
+            time_since_start: 0.0,
+            world,
+            pointer_policy: PointerPolicy::default(),
+            log: Box::new(StdoutLogSink),
+            recording: None,
+            recorded_input: None,
+            tracer: Tracer::default(),
+            work: VecDeque::new(),
+            scheduled: vec![],
+            breakpoints: vec![],
+            paused: false,
+        }
+    }
+
+    pub fn world(&self) -> &W {
+        &self.world
+    }
+
+    pub fn world_mut(&mut self) -> &mut W {
+        &mut self.world
+    }
+
+    /// Restricts which pointers the graph may read and write. Everything that
+    /// is not read-only is allowed by default.
+    pub fn set_pointer_policy(&mut self, pointer_policy: PointerPolicy) {
+        self.pointer_policy = pointer_policy;
+    }
+
+    pub fn pointer_policy(&self) -> &PointerPolicy {
+        &self.pointer_policy
+    }
+
+    /// Replaces where `debug/log` and `custom/print` write to, stdout by default.
+    pub fn set_log_sink(&mut self, log: impl LogSink + 'static) {
+        self.log = Box::new(log);
+    }
+
+    /// Adds a sink that is told about every node activation, computed value,
+    /// fired flow and pointer write.
+    pub fn add_trace_sink(&mut self, sink: impl TraceSink + 'static) {
+        self.tracer.add_sink(sink);
+    }
+
+    /// Declares a graph variable and returns its index.
+    pub fn add_variable(&mut self, declaration: VariableDeclaration) -> Result<usize, VariableError> {
+        self.variables.add(declaration)
+    }
+
+    pub fn variables(&self) -> &Variables {
+        &self.variables
+    }
+
+    pub fn variable(&self, index: usize) -> Option<&Value> {
+        self.variables.get(index)
+    }
+
+    pub fn variable_by_name(&self, name: &str) -> Option<&Value> {
+        self.variables.get(self.variables.index(name)?)
+    }
+
+    /// Sets a graph variable from the host, cancelling any interpolation of
+    /// the variable. The
+    /// value is checked right away, but set like any other host input: once
+    /// execution continues, if it is paused.
+    pub fn set_variable(&mut self, index: usize, value: Value) -> Result<(), VariableError> {
+        self.variables.check(index, &value)?;
+        self.record(HostInput::SetVariable {
+            index,
+            value: value.clone(),
+        });
+        self.schedule(Work::SetVariable {
+            index,
+            value,
+        });
+        Ok(())
+    }
+
+    pub fn set_variable_by_name(&mut self, name: &str, value: Value) -> Result<(), VariableError> {
+        let index = self.variables.index(name).ok_or_else(|| VariableError::UnknownName(name.to_string()))?;
+        self.set_variable(index, value)
+    }
+
+    /// Declares a custom event and returns its index.
+    pub fn add_event(&mut self, declaration: EventDeclaration) -> usize {
+        self.events.push(declaration);
+        self.events.len() - 1
+    }
+
+    pub fn events(&self) -> &[EventDeclaration] {
+        &self.events
+    }
+
+    pub fn event_index(&self, id: &str) -> Option<usize> {
+        self.events.iter().position(|a| a.id == id)
+    }
+
+    /// Queues a custom event from the host. It reaches `event/receive` nodes on the next tick.
+    pub fn send_event(&mut self, event: usize, values: HashMap<String, Value>) -> Result<(), EventError> {
+        self.check_event(event, &values)?;
+        self.record(HostInput::SendEvent {
+            event,
+            values: values.clone(),
+        });
+        self.schedule(Work::QueueEvent {
+            event,
+            values,
+        });
+        Ok(())
+    }
+
+    /// Changes a property of the scene from the host, cancelling any
+    /// interpolation of it. Unlike writing to [`Runtime::world_mut`] directly,
+    /// this is recorded, so that a replay sees the same scene, and waits for
+    /// execution to continue if it is paused. `None` if the world has no such
+    /// property of the type of `value`.
+    pub fn write_pointer(&mut self, pointer: &str, value: Value) -> Option<()> {
+        self.world.read_pointer(pointer).filter(|a| a.value_type() == value.value_type())?;
+        self.record(HostInput::WritePointer {
+            pointer: pointer.to_string(),
+            value: value.clone(),
+        });
+        self.schedule(Work::WritePointer {
+            pointer: pointer.to_string(),
+            value,
+        });
+        Some(())
+    }
+
+    /// Calls `callback` whenever the graph sends the custom event `event`.
+    pub fn subscribe(&mut self, event: usize, callback: impl FnMut(&CustomEvent) + 'static) {
+        self.subscribers.push((event, Box::new(callback)));
+    }
+
+    /// Activates every `event/onStart` node. Only the first call has any effect.
+    pub fn start(&mut self) {
+        if !self.started {
+            self.record(HostInput::Start);
+            self.schedule(Work::Start);
+        }
+    }
+
+    /// Advances the world and the graph by `dt` seconds: handles the events the
+    /// world raised, dispatches the pending custom events, advances the
+    /// interpolations, then activates every `event/onTick` node. Starts the
+    /// graph first if that has not happened yet. Time stands still while
+    /// execution is paused, so ticks are ignored then.
+    pub fn tick(&mut self, dt: f32) {
+        self.tick_with(dt, None);
+    }
+
+    /// Ticks with `replayed` in place of the events the world raises, which
+    /// are recorded otherwise.
+    fn tick_with(&mut self, dt: f32, replayed: Option<Vec<WorldEvent>>) {
+        if self.paused {
+            return;
+        }
+        self.record(HostInput::Tick {
+            dt,
+            world_events: vec![],
+        });
+        self.schedule(Work::Tick {
+            dt,
+            replayed,
+        });
+    }
+
+    /// Ticks by however much time passed in the world.
+    pub fn update(&mut self) {
+        let dt = self.world.delta_time();
+        self.tick(dt);
+    }
+
+    /// Reports that `controller` selected the glTF node `node` at `point`,
+    /// with a ray cast from `ray_origin`. `event/onSelect` nodes of `node` and
+    /// then of its ancestors are activated, until one of them stops the
+    /// propagation. Nothing happens if the node or one of its ancestors is not
+    /// selectable or hidden.
+    pub fn select(&mut self, node: usize, controller: i32, point: [f32; 3], ray_origin: [f32; 3]) {
+        self.record(HostInput::Select {
+            node,
+            controller,
+            point,
+            ray_origin,
+        });
+        self.schedule(Work::Select {
+            node,
+            controller,
+            point,
+            ray_origin,
+        });
+    }
+
+    /// Reports the node `controller` hovers now, if any. `event/onHoverOut`
+    /// nodes are activated for the previously hovered node and its ancestors
+    /// that are no longer hovered, then `event/onHoverIn` nodes for the newly
+    /// hovered node and its ancestors that were not hovered before, each until
+    /// one of them stops the propagation. A node that is not hoverable or
+    /// hidden, or has an ancestor that is, counts as no node.
+    pub fn hover(&mut self, node: Option<usize>, controller: i32) {
+        self.record(HostInput::Hover {
+            node,
+            controller,
+        });
+        self.schedule(Work::Hover {
+            node,
+            controller,
+        });
+    }
+
+    /// Reports that `completion` happened, activating every flow waiting for it.
+    pub fn complete(&mut self, completion: &Completion) {
+        self.record(HostInput::Complete(completion.clone()));
+        self.schedule(Work::Complete(completion.clone()));
+    }
+
+    /// Reports that the animation with index `animation` finished playing, or
+    /// reached the time it was told to stop at.
+    pub fn complete_animation(&mut self, animation: i32) {
+        self.complete(&Completion::Animation(animation));
+    }
+
+    /// Pauses execution before every activation `breakpoint` matches. While
+    /// paused, host inputs other than ticks are queued until execution
+    /// continues with [`Runtime::step`] or [`Runtime::resume`].
+    pub fn add_breakpoint(&mut self, breakpoint: Breakpoint) {
+        self.breakpoints.push(breakpoint);
+    }
+
+    pub fn remove_breakpoint(&mut self, breakpoint: &Breakpoint) {
+        self.breakpoints.retain(|a| a != breakpoint);
+    }
+
+    pub fn breakpoints(&self) -> &[Breakpoint] {
+        &self.breakpoints
+    }
+
+    /// The activation execution is paused before, if it is.
+    pub fn paused(&self) -> Option<&Activation> {
+        self.scheduled.last().filter(|_| self.paused)
+    }
+
+    /// The activations waiting to run, the next one last.
+    pub fn scheduled(&self) -> &[Activation] {
+        &self.scheduled
+    }
+
+    /// Runs the activation execution is paused before, then pauses again
+    /// before the next one, if there is one.
+    pub fn step(&mut self) {
+        if self.paused {
+            self.run(true, true);
+        }
+    }
+
+    /// Continues execution until the next breakpoint.
+    pub fn resume(&mut self) {
+        if self.paused {
+            self.run(true, false);
+        }
+    }
+
+    pub fn interpolations(&self) -> &[Interpolation] {
+        &self.interpolations
+    }
+
+    pub fn pending_flows(&self) -> &[PendingFlow] {
+        &self.pending_flows
+    }
+
+    pub fn time_since_start(&self) -> f32 {
+        self.time_since_start
+    }
+
+    pub fn existing_values(&self) -> &ExistingValues {
+        &self.existing_values
+    }
+
+    /// Captures the state needed to continue execution later, see [`Snapshot`].
+    /// Take it while execution is not paused: what is left of the paused host
+    /// input is not part of it.
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            existing_values: self.existing_values.clone(),
+            variables: self.variables.clone(),
+            node_states: self.node_behaviors.0.iter()
+                .filter_map(|(node_id, behavior)| Some((*node_id, behavior.state()?)))
+                .collect(),
+            pending_events: self.pending_events.clone(),
+            pending_flows: self.pending_flows.clone(),
+            interpolations: self.interpolations.clone(),
+            hovered: self.hovered.clone(),
+            started: self.started,
+            time_since_start: self.time_since_start,
+        }
+    }
+
+    /// Continues from `snapshot`, which has to be taken from a runtime built
+    /// from the same graph. Nothing but the state of nodes up to the failing
+    /// one is restored if it is not.
+    pub fn restore(&mut self, snapshot: Snapshot) -> Result<(), SnapshotError> {
+        if let Some(node_id) = snapshot.node_states.keys().find(|a| !self.node_behaviors.0.contains_key(a)) {
+            return Err(SnapshotError::UnknownNode(*node_id));
+        }
+        for (node_id, state) in snapshot.node_states {
+            self.node_behaviors.0.get_mut(&node_id).unwrap()
+                .restore_state(state)
+                .map_err(|source| SnapshotError::NodeState { node_id, source })?;
+        }
+        self.existing_values = snapshot.existing_values;
+        self.variables = snapshot.variables;
+        self.pending_events = snapshot.pending_events;
+        self.pending_flows = snapshot.pending_flows;
+        self.interpolations = snapshot.interpolations;
+        self.hovered = snapshot.hovered;
+        self.started = snapshot.started;
+        self.time_since_start = snapshot.time_since_start;
+        Ok(())
+    }
+
+    /// Starts recording every host input along with the logs, events and
+    /// pointer writes it causes, dropping any recording in progress.
+    pub fn start_recording(&mut self) {
+        self.recording = Some(Recording::default());
+        self.recorded_input = None;
+        self.work.retain(|a| !matches!(a, Work::Record(_)));
+    }
+
+    pub fn stop_recording(&mut self) -> Option<Recording> {
+        self.recording.take()
+    }
+
+    pub fn recording(&self) -> Option<&Recording> {
+        self.recording.as_ref()
+    }
+
+    /// Does what the host did to cause `input`, as when replaying a recording.
+    pub fn apply(&mut self, input: HostInput) {
+        match input {
+            HostInput::Start => self.start(),
+            HostInput::Tick { dt, world_events } => self.tick_with(dt, Some(world_events)),
+            HostInput::SendEvent { event, values } => {
+                let _ = self.send_event(event, values);
+            }
+            HostInput::SetVariable { index, value } => {
+                let _ = self.set_variable(index, value);
+            }
+            HostInput::Select { node, controller, point, ray_origin } => self.select(node, controller, point, ray_origin),
+            HostInput::Hover { node, controller } => self.hover(node, controller),
+            HostInput::Complete(completion) => self.complete(&completion),
+            HostInput::WritePointer { pointer, value } => {
+                self.write_pointer(&pointer, value);
+            }
+        }
+    }
+
+    /// Records `input`, attributing the outputs of the work queued after it to it.
+    fn record(&mut self, input: HostInput) {
+        if let Some(recording) = &mut self.recording {
+            recording.inputs.push(RecordedInput {
+                input,
+                outputs: Outputs::default(),
+            });
+            self.work.push_back(Work::Record(recording.inputs.len() - 1));
+        }
+    }
+
+    /// The input whose work is being carried out, if recording.
+    fn recorded(&mut self) -> Option<&mut RecordedInput> {
+        self.recording.as_mut()?.inputs.get_mut(self.recorded_input?)
+    }
+
+    /// Where the outputs of the input being recorded go, if recording.
+    fn outputs(&mut self) -> Option<&mut Outputs> {
+        self.recorded().map(|a| &mut a.outputs)
+    }
+
+    /// Queues `work` and carries it out, unless execution is paused.
+    fn schedule(&mut self, work: Work) {
+        self.work.push_back(work);
+        self.run(false, false);
+    }
+
+    /// Queues `work` ahead of everything else, in order.
+    fn schedule_next(&mut self, work: Vec<Work>) {
+        for work in work.into_iter().rev() {
+            self.work.push_front(work);
+        }
+    }
+
+    /// Runs scheduled activations and carries out queued work until there is
+    /// none left, or pauses before an activation with a breakpoint. `resume`
+    /// lets the next activation run regardless, to move past the one execution
+    /// is paused before, and `stepping` pauses before any activation after it.
+    fn run(&mut self, mut resume: bool, stepping: bool) {
+        if self.paused && !resume {
+            return;
+        }
+        self.paused = false;
+        loop {
+            if let Some(next) = self.scheduled.last() {
+                if !resume && (stepping || self.breakpoints.iter().any(|a| a.matches(next))) {
+                    self.paused = true;
+                    return;
+                }
+                resume = false;
+                self.context(|ctx| ctx.step());
+            } else if let Some(work) = self.work.pop_front() {
+                self.perform(work);
+            } else {
+                return;
+            }
+        }
+    }
+
+    fn perform(&mut self, work: Work) {
+        match work {
+            Work::Record(index) => self.recorded_input = Some(index),
+            Work::Start => {
+                if !self.started {
+                    self.started = true;
+                    self.schedule_next(vec![Work::Dispatch(RuntimeEvent::Start), Work::Publish]);
+                }
+            }
+            Work::Tick { dt, replayed } => self.schedule_next(vec![Work::Start, Work::Advance { dt, replayed }]),
+            Work::Advance { dt, replayed } => self.advance(dt, replayed),
+            Work::Select { node, controller, point, ray_origin } => {
+                let ancestors = self.ancestors(node);
+                if !ancestors.iter().all(|a| self.world.selectable(*a) && self.world.visible(*a)) {
+                    return;
+                }
+                let events = ancestors.into_iter().map(|target| RuntimeEvent::Select {
+                    target,
+                    selected: node,
+                    controller,
+                    point,
+                    ray_origin,
+                }).collect();
+                self.schedule_next(vec![Work::Propagate { previous: None, events }, Work::Publish]);
+            }
+            Work::Hover { node, controller } => self.hover_node(node, controller),
+            Work::Complete(completion) => {
+                let (ready, pending) = std::mem::take(&mut self.pending_flows)
+                    .into_iter()
+                    .partition::<Vec<_>, _>(|a| a.completion == completion);
+                self.pending_flows = pending;
+                let mut work = ready.into_iter()
+                    .map(|PendingFlow { node_id, name, .. }| Work::Fire { node_id, name })
+                    .collect::<Vec<_>>();
+                work.push(Work::Publish);
+                self.schedule_next(work);
+            }
+            Work::SetVariable { index, value } => {
+                if let Err(error) = self.variables.set(index, value) {
+                    self.log(Severity::Error, &error.to_string());
+                    return;
+                }
+                self.interpolations.retain(|a| a.target != InterpolationTarget::Variable(index));
+            }
+            Work::WritePointer { pointer, value } => {
+                if self.world.write_pointer(&pointer, value).is_none() {
+                    self.log(Severity::Error, &format!("cannot write `{pointer}`"));
+                    return;
+                }
+                let target = InterpolationTarget::Pointer(pointer);
+                self.interpolations.retain(|a| a.target != target);
+            }
+            Work::QueueEvent { event, values } => {
+                if let Err(error) = self.queue_event(event, values) {
+                    self.log(Severity::Error, &error.to_string());
+                }
+            }
+            Work::DispatchPendingEvents => {
+                let work = std::mem::take(&mut self.pending_events)
+                    .into_iter()
+                    .map(|a| Work::Dispatch(RuntimeEvent::Custom(a)))
+                    .collect();
+                self.schedule_next(work);
+            }
+            Work::AdvanceInterpolations(dt) => self.advance_interpolations(dt),
+            Work::Dispatch(event) => {
+                let work = self.node_ids.iter()
+                    .map(|node_id| Work::HandleEvent { node_id: *node_id, event: event.clone() })
+                    .collect();
+                self.schedule_next(work);
+            }
+            Work::HandleEvent { node_id, event } => {
+                let mut behavior = self.node_behaviors.0.remove(&node_id).unwrap();
+                self.context(|ctx| behavior.handle_event(ctx, &event));
+                self.node_behaviors.0.insert(node_id, behavior);
+            }
+            Work::Propagate { previous, mut events } => {
+                if previous.is_some_and(|event| self.node_behaviors.0.values().any(|a| a.stops_propagation(&event))) {
+                    return;
+                }
+                if let Some(event) = events.pop_front() {
+                    self.schedule_next(vec![Work::Dispatch(event.clone()), Work::Propagate { previous: Some(event), events }]);
+                }
+            }
+            Work::Fire { node_id, name } => {
+                self.context(|ctx| ctx.fire(node_id, &name));
+            }
+            Work::Publish => self.publish_sent_events(),
+        }
+    }
+
+    /// Moves time and the world on, then queues the rest of the tick.
+    fn advance(&mut self, dt: f32, replayed: Option<Vec<WorldEvent>>) {
+        self.time_since_start += dt;
+        self.world.advance(dt);
+        let world_events = self.world.take_events();
+        let world_events = replayed.unwrap_or(world_events);
+        if let Some(RecordedInput { input: HostInput::Tick { world_events: recorded, .. }, .. }) = self.recorded() {
+            recorded.clone_from(&world_events);
+        }
+        let mut work = world_events.into_iter().map(|event| match event {
+            WorldEvent::AnimationCompleted(animation) => Work::Complete(Completion::Animation(animation)),
+            WorldEvent::Custom { event, values } => Work::QueueEvent { event, values },
+            WorldEvent::Select { node, controller, point, ray_origin } => Work::Select { node, controller, point, ray_origin },
+            WorldEvent::Hover { node, controller } => Work::Hover { node, controller },
+        }).collect::<Vec<_>>();
+        work.extend([
+            Work::DispatchPendingEvents,
+            Work::AdvanceInterpolations(dt),
+            Work::Dispatch(RuntimeEvent::Tick {
+                time_since_start: self.time_since_start,
+                time_since_last_tick: dt,
+            }),
+            Work::Publish,
+        ]);
+        self.schedule_next(work);
+    }
+
+    fn hover_node(&mut self, node: Option<usize>, controller: i32) {
+        let node = node.filter(|node| self.ancestors(*node).iter().all(|a| self.world.hoverable(*a) && self.world.visible(*a)));
+        let previous = self.hovered.get(&controller).copied();
+        if node == previous {
+            return;
+        }
+        let old = previous.map(|a| self.ancestors(a)).unwrap_or_default();
+        let new = node.map(|a| self.ancestors(a)).unwrap_or_default();
+        match node {
+            Some(node) => self.hovered.insert(controller, node),
+            None => self.hovered.remove(&controller),
+        };
+        let mut work = vec![];
+        if let Some(hovered) = previous {
+            let events = old.iter().copied().filter(|a| !new.contains(a)).map(|target| RuntimeEvent::HoverOut {
+                target,
+                hovered,
+                controller,
+            }).collect();
+            work.push(Work::Propagate { previous: None, events });
+        }
+        if let Some(hovered) = node {
+            let events = new.iter().copied().filter(|a| !old.contains(a)).map(|target| RuntimeEvent::HoverIn {
+                target,
+                hovered,
+                controller,
+            }).collect();
+            work.push(Work::Propagate { previous: None, events });
+        }
+        work.push(Work::Publish);
+        self.schedule_next(work);
+    }
+
+    fn check_event(&self, event: usize, values: &HashMap<String, Value>) -> Result<(), EventError> {
+        self.events.get(event)
+            .ok_or(EventError::UnknownEvent(event))?
+            .check(values)
+    }
+
+    fn queue_event(&mut self, event: usize, values: HashMap<String, Value>) -> Result<(), EventError> {
+        self.check_event(event, &values)?;
+        self.pending_events.push_back(CustomEvent {
+            event,
+            values,
+        });
+        Ok(())
+    }
+
+    fn log(&mut self, severity: Severity, message: &str) {
+        if let Some(outputs) = self.outputs() {
+            outputs.logs.push(LogEntry {
+                severity,
+                message: message.to_string(),
+            });
+        }
+        self.log.log(severity, message);
+    }
+
+    /// Writes every interpolation's next value, then activates `done` of the
+    /// ones that finished.
+    fn advance_interpolations(&mut self, dt: f32) {
+        let mut finished = vec![];
+        for interpolation in &mut self.interpolations {
+            let (value, done) = interpolation.advance(dt);
+            match &interpolation.target {
+                InterpolationTarget::Pointer(pointer) => {
+                    if self.world.write_pointer(pointer, value.clone()).is_some() {
+                        self.tracer.trace(|| TraceKind::PointerWritten {
+                            pointer: pointer.clone(),
+                            value: value.clone(),
+                        });
+                        if let Some(outputs) = self.recording.as_mut().zip(self.recorded_input).and_then(|(a, b)| a.inputs.get_mut(b)).map(|a| &mut a.outputs) {
+                            outputs.pointer_writes.push(PointerWrite {
+                                pointer: pointer.clone(),
+                                value,
+                            });
+                        }
+                    }
+                }
+                InterpolationTarget::Variable(index) => {
+                    let _ = self.variables.set(*index, value);
+                }
+            }
+            if done {
+                finished.push((interpolation.node_id, interpolation.target.clone()));
+            }
+        }
+        // A node may interpolate several targets at once, and only one
+        // interpolation runs per target.
+        self.interpolations.retain(|a| !finished.iter().any(|(_, target)| *target == a.target));
+        let mut work = finished.into_iter()
+            .map(|(node_id, _)| Work::Fire { node_id, name: "done".to_string() })
+            .collect::<Vec<_>>();
+        work.push(Work::Publish);
+        self.schedule_next(work);
+    }
+
+    /// `node` followed by its ancestors, from the closest.
+    fn ancestors(&self, node: usize) -> Vec<usize> {
+        let mut ancestors = vec![node];
+        while let Some(parent) = self.world.parent(*ancestors.last().unwrap()) {
+            if ancestors.contains(&parent) {
+                break;
+            }
+            ancestors.push(parent);
+        }
+        ancestors
+    }
+
+    /// Runs `f` with the context nodes execute in. While recording, the log
+    /// and the pointer writes are captured on their way to the host.
+    fn context<R>(&mut self, f: impl FnOnce(&mut ExecutionContext) -> R) -> R {
+        let (mut recording_world, mut recording_log);
+        let outputs = self.recording.as_mut().zip(self.recorded_input).and_then(|(a, b)| a.inputs.get_mut(b));
+        let (world, log): (&mut dyn World, &mut dyn LogSink) = match outputs {
+            Some(RecordedInput { outputs: Outputs { logs, pointer_writes, .. }, .. }) => {
+                recording_world = RecordingWorld {
+                    world: &mut self.world,
+                    writes: pointer_writes,
+                };
+                recording_log = RecordingLogSink {
+                    log: self.log.as_mut(),
+                    logs,
+                };
+                (&mut recording_world, &mut recording_log)
+            }
+            None => (&mut self.world, self.log.as_mut()),
+        };
+        let mut ctx = ExecutionContext {
+            graph: &self.graph,
+            existing_values: &mut self.existing_values,
+            node_behaviors: &mut self.node_behaviors,
+            variables: &mut self.variables,
+            events: &mut self.sent_events,
+            pending_flows: &mut self.pending_flows,
+            interpolations: &mut self.interpolations,
+            world,
+            pointer_policy: &self.pointer_policy,
+            log,
+            tracer: &mut self.tracer,
+            scheduled: &mut self.scheduled,
+        };
+        f(&mut ctx)
+    }
+
+    /// Hands the events the graph sent to the host's subscribers and queues them
+    /// for the graph's own `event/receive` nodes. Events that do not match
+    /// their declaration are logged as errors and dropped.
+    fn publish_sent_events(&mut self) {
+        for event in std::mem::take(&mut self.sent_events) {
+            let checked = self.events.get(event.event)
+                .ok_or(EventError::UnknownEvent(event.event))
+                .and_then(|a| a.check(&event.values));
+            if let Err(error) = checked {
+                self.log(Severity::Error, &error.to_string());
+                continue;
+            }
+            if let Some(outputs) = self.outputs() {
+                outputs.events.push(event.clone());
+            }
+            for (_, callback) in self.subscribers.iter_mut().filter(|(a, _)| *a == event.event) {
+                callback(&event);
+            }
+            self.pending_events.push_back(event);
+        }
+    }
+}
+
+#[test]
+fn test_start_and_tick() {
+    use crate::{MathPi, NodeBehavior, OnStartNode, OnTickNode, PrintNode};
+
+    let mut graph = Graph::new();
+    let mut node_behaviors = NodeBehaviors(HashMap::new());
+
+    let start_id = 0;
+    let tick_id = 1;
+    let pi_id = 2;
+    let print_id = 3;
+
+    let start = OnStartNode::new(start_id);
+    let tick = OnTickNode::new(tick_id);
+    let pi = MathPi::new(pi_id);
+    let print = PrintNode::new(print_id);
+
+    graph
+        .add_node(start.node())
+        .add_node(tick.node())
+        .add_node(pi.node())
+        .add_node(print.node())
+        .link_flow(start_id, "out", print_id, "print_input")
+        .link_value(pi_id, "value", print_id, "print_value");
+
+    node_behaviors.add(Box::new(start));
+    node_behaviors.add(Box::new(tick));
+    node_behaviors.add(Box::new(pi));
+    node_behaviors.add(Box::new(print));
+
+    let mut runtime = Runtime::new(graph.resolve().unwrap(), node_behaviors);
+    runtime.start();
+    assert!(matches!(runtime.existing_values().get_value(pi_id, "value"), Some(Value::Float(_))));
+
+    runtime.tick(0.5);
+    runtime.tick(0.25);
+    assert_eq!(runtime.existing_values().get_value(tick_id, "timeSinceStart"), Some(&Value::Float(0.75)));
+    assert_eq!(runtime.existing_values().get_value(tick_id, "timeSinceLastTick"), Some(&Value::Float(0.25)));
+}
+
+#[test]
+fn test_failing_node() {
+    use crate::{CollectingLogSink, MathAdd, MathPi, NodeBehavior, OnSelectNode, OnStartNode, PrintNode};
+
+    // `a` of the add reads a value nothing has written yet, so the add cannot
+    // compute its sum and the print has nothing to print.
+    let mut graph = Graph::new();
+    let mut node_behaviors = NodeBehaviors(HashMap::new());
+    let start = OnStartNode::new(0);
+    let select = OnSelectNode::new(1, 0, false);
+    let pi = MathPi::new(2);
+    let add = MathAdd::new(3);
+    let print = PrintNode::new(5);
+    graph
+        .add_node(start.node())
+        .add_node(select.node())
+        .add_node(pi.node())
+        .add_node(add.node())
+        .add_node(print.node())
+        .link_flow(0, "out", 5, "print_input")
+        .link_value(1, "selectedNodeIndex", 3, "a")
+        .link_value(2, "value", 3, "b")
+        .link_value(3, "value", 5, "print_value");
+    node_behaviors.add(Box::new(start));
+    node_behaviors.add(Box::new(select));
+    node_behaviors.add(Box::new(pi));
+    node_behaviors.add(Box::new(add));
+    node_behaviors.add(Box::new(print));
+
+    let sink = CollectingLogSink::default();
+    let mut runtime = Runtime::new(graph.resolve().unwrap(), node_behaviors);
+    runtime.set_log_sink(sink.clone());
+    runtime.start();
+    assert_eq!(sink.messages(), vec![
+        "node 3 (math/add) failed to compute its values",
+        "node 5 (custom/print) failed to be activated",
+    ]);
+}
+
+#[test]
+fn test_finished_interpolations() {
+    let interpolation = |target, duration| Interpolation {
+        node_id: 0,
+        target,
+        from: Value::Float(0.0),
+        to: Value::Float(1.0),
+        duration,
+        elapsed: 0.0,
+        p1: [0.0, 0.0],
+        p2: [1.0, 1.0],
+        slerp: false,
+    };
+
+    // The same node interpolates two variables, one finishing before the other.
+    let mut runtime = Runtime::new(Graph::new().resolve().unwrap(), NodeBehaviors(HashMap::new()));
+    for name in ["a", "b"] {
+        runtime.add_variable(VariableDeclaration {
+            name: name.to_string(),
+            value_type: crate::ValueType::Float,
+            value: Value::Float(0.0),
+        }).unwrap();
+    }
+    runtime.interpolations = vec![
+        interpolation(InterpolationTarget::Variable(0), 1.0),
+        interpolation(InterpolationTarget::Variable(1), 2.0),
+    ];
+    runtime.tick(1.0);
+    assert_eq!(runtime.variable(0), Some(&Value::Float(1.0)));
+    assert_eq!(runtime.interpolations().len(), 1);
+    assert_eq!(runtime.interpolations()[0].target, InterpolationTarget::Variable(1));
+
+    // A host write wins over the interpolation of the same variable.
+    runtime.set_variable(1, Value::Float(0.25)).unwrap();
+    assert!(runtime.interpolations().is_empty());
+    runtime.tick(0.5);
+    assert_eq!(runtime.variable(1), Some(&Value::Float(0.25)));
+}
@@ -0,0 +1,253 @@
+use serde::{Deserialize, Serialize};
+use crate::{node, ExecutionContext, NodeId, RuntimeEvent};
+
+/// `event/onSelect`, activated when `node_index` or one of its descendants is
+/// selected, see [`crate::Runtime::select`]. With `stop_propagation` the
+/// selection does not reach the ancestors of `node_index`.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct OnSelectNode {
+    node_id: NodeId,
+    node_index: usize,
+    stop_propagation: bool,
+}
+
+impl OnSelectNode {
+    pub fn new(node_id: NodeId, node_index: usize, stop_propagation: bool) -> Self {
+        Self {
+            node_id,
+            node_index,
+            stop_propagation,
+        }
+    }
+}
+
+node! {
+    OnSelectNode {
+        op: "event/onSelect",
+        kind: Event,
+        description: "Activated when a node or one of its descendants is selected.",
+        output_values: {
+            set_selected_node_index = "selectedNodeIndex": i32,
+            set_controller_index = "controllerIndex": i32,
+            set_selection_point = "selectionPoint": [f32; 3],
+            set_selection_ray_origin = "selectionRayOrigin": [f32; 3],
+        },
+        output_flows: ["out"],
+        configuration: |node| { "nodeIndex": Int(node.node_index as i32), "stopPropagation": Bool(node.stop_propagation) },
+        new_node: |node_id, configuration| Self::new(
+            node_id,
+            configuration.index("nodeIndex")?.unwrap_or(0),
+            configuration.bool("stopPropagation")?.unwrap_or(false),
+        ),
+    }
+    impl NodeBehavior {
+        fn value(&mut self, _: &mut ExecutionContext) -> Option<()> {
+            Some(())
+        }
+
+        fn activate_input_node(&mut self, _: &mut ExecutionContext) -> Option<()> {
+            panic!("cannot activate this node");
+        }
+
+        fn handle_event(&mut self, ctx: &mut ExecutionContext, event: &RuntimeEvent) -> Option<()> {
+            match event {
+                RuntimeEvent::Select { target, selected, controller, point, ray_origin } if *target == self.node_index => {
+                    self.set_selected_node_index(ctx, *selected as i32);
+                    self.set_controller_index(ctx, *controller);
+                    self.set_selection_point(ctx, *point);
+                    self.set_selection_ray_origin(ctx, *ray_origin);
+                    ctx.fire(self.node_id, "out")
+                }
+                _ => Some(()),
+            }
+        }
+
+        fn stops_propagation(&self, event: &RuntimeEvent) -> bool {
+            matches!(event, RuntimeEvent::Select { target, .. } if self.stop_propagation && *target == self.node_index)
+        }
+    }
+}
+
+/// `event/onHoverIn`, activated when a controller starts hovering `node_index`
+/// or one of its descendants, see [`crate::Runtime::hover`]. With
+/// `stop_propagation` the hover does not reach the ancestors of `node_index`.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct OnHoverInNode {
+    node_id: NodeId,
+    node_index: usize,
+    stop_propagation: bool,
+}
+
+impl OnHoverInNode {
+    pub fn new(node_id: NodeId, node_index: usize, stop_propagation: bool) -> Self {
+        Self {
+            node_id,
+            node_index,
+            stop_propagation,
+        }
+    }
+}
+
+node! {
+    OnHoverInNode {
+        op: "event/onHoverIn",
+        kind: Event,
+        description: "Activated when a controller starts hovering a node or one of its descendants.",
+        output_values: {
+            set_hover_node_index = "hoverNodeIndex": i32,
+            set_controller_index = "controllerIndex": i32,
+        },
+        output_flows: ["out"],
+        configuration: |node| { "nodeIndex": Int(node.node_index as i32), "stopPropagation": Bool(node.stop_propagation) },
+        new_node: |node_id, configuration| Self::new(
+            node_id,
+            configuration.index("nodeIndex")?.unwrap_or(0),
+            configuration.bool("stopPropagation")?.unwrap_or(false),
+        ),
+    }
+    impl NodeBehavior {
+        fn value(&mut self, _: &mut ExecutionContext) -> Option<()> {
+            Some(())
+        }
+
+        fn activate_input_node(&mut self, _: &mut ExecutionContext) -> Option<()> {
+            panic!("cannot activate this node");
+        }
+
+        fn handle_event(&mut self, ctx: &mut ExecutionContext, event: &RuntimeEvent) -> Option<()> {
+            match event {
+                RuntimeEvent::HoverIn { target, hovered, controller } if *target == self.node_index => {
+                    self.set_hover_node_index(ctx, *hovered as i32);
+                    self.set_controller_index(ctx, *controller);
+                    ctx.fire(self.node_id, "out")
+                }
+                _ => Some(()),
+            }
+        }
+
+        fn stops_propagation(&self, event: &RuntimeEvent) -> bool {
+            matches!(event, RuntimeEvent::HoverIn { target, .. } if self.stop_propagation && *target == self.node_index)
+        }
+    }
+}
+
+/// `event/onHoverOut`, activated when a controller stops hovering
+/// `node_index` or one of its descendants, see [`crate::Runtime::hover`].
+/// With `stop_propagation` the change does not reach the ancestors of
+/// `node_index`.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct OnHoverOutNode {
+    node_id: NodeId,
+    node_index: usize,
+    stop_propagation: bool,
+}
+
+impl OnHoverOutNode {
+    pub fn new(node_id: NodeId, node_index: usize, stop_propagation: bool) -> Self {
+        Self {
+            node_id,
+            node_index,
+            stop_propagation,
+        }
+    }
+}
+
+node! {
+    OnHoverOutNode {
+        op: "event/onHoverOut",
+        kind: Event,
+        description: "Activated when a controller stops hovering a node or one of its descendants.",
+        output_values: {
+            set_hover_node_index = "hoverNodeIndex": i32,
+            set_controller_index = "controllerIndex": i32,
+        },
+        output_flows: ["out"],
+        configuration: |node| { "nodeIndex": Int(node.node_index as i32), "stopPropagation": Bool(node.stop_propagation) },
+        new_node: |node_id, configuration| Self::new(
+            node_id,
+            configuration.index("nodeIndex")?.unwrap_or(0),
+            configuration.bool("stopPropagation")?.unwrap_or(false),
+        ),
+    }
+    impl NodeBehavior {
+        fn value(&mut self, _: &mut ExecutionContext) -> Option<()> {
+            Some(())
+        }
+
+        fn activate_input_node(&mut self, _: &mut ExecutionContext) -> Option<()> {
+            panic!("cannot activate this node");
+        }
+
+        fn handle_event(&mut self, ctx: &mut ExecutionContext, event: &RuntimeEvent) -> Option<()> {
+            match event {
+                RuntimeEvent::HoverOut { target, hovered, controller } if *target == self.node_index => {
+                    self.set_hover_node_index(ctx, *hovered as i32);
+                    self.set_controller_index(ctx, *controller);
+                    ctx.fire(self.node_id, "out")
+                }
+                _ => Some(()),
+            }
+        }
+
+        fn stops_propagation(&self, event: &RuntimeEvent) -> bool {
+            matches!(event, RuntimeEvent::HoverOut { target, .. } if self.stop_propagation && *target == self.node_index)
+        }
+    }
+}
+
+#[test]
+fn test_select_and_hover() {
+    use std::collections::HashMap;
+    use crate::{CollectingLogSink, GltfWorld, Graph, LogNode, NodeBehavior, NodeBehaviors, Runtime, Severity, Value, World};
+
+    let mut graph = Graph::new();
+    let mut node_behaviors = NodeBehaviors(HashMap::new());
+
+    let mut add = |event: Box<dyn NodeBehavior>, output: &str, message: &str| {
+        let event_id = event.node_id();
+        let log = LogNode::new(event_id + 100, message, Severity::Info);
+        graph
+            .add_node(event.node())
+            .add_node(log.node())
+            .link_flow(event_id, "out", event_id + 100, "in")
+            .link_value(event_id, output, event_id + 100, "node");
+        node_behaviors.add(event);
+        node_behaviors.add(Box::new(log));
+    };
+    add(Box::new(OnSelectNode::new(0, 0, false)), "selectedNodeIndex", "0 saw {node}");
+    add(Box::new(OnSelectNode::new(1, 1, true)), "selectedNodeIndex", "1 saw {node}");
+    add(Box::new(OnHoverInNode::new(2, 0, false)), "hoverNodeIndex", "in {node}");
+    add(Box::new(OnHoverOutNode::new(3, 0, false)), "hoverNodeIndex", "out {node}");
+
+    // 0 is the parent of 1, which is the parent of 2
+    let world = GltfWorld::from_json(r#"{ "nodes": [{ "children": [1] }, { "children": [2] }, {}] }"#).unwrap();
+    let sink = CollectingLogSink::default();
+    let mut runtime = Runtime::with_world(graph.resolve().unwrap(), node_behaviors, world);
+    runtime.set_log_sink(sink.clone());
+
+    runtime.select(2, 0, [0.0; 3], [0.0; 3]);
+    runtime.select(0, 0, [0.0; 3], [0.0; 3]);
+    assert_eq!(sink.messages(), vec!["1 saw 2", "0 saw 0"]);
+    sink.clear();
+
+    runtime.world_mut().write_pointer("/nodes/1/extensions/KHR_node_selectability/selectable", Value::Bool(false)).unwrap();
+    runtime.select(2, 0, [0.0; 3], [0.0; 3]);
+    assert!(sink.messages().is_empty());
+
+    runtime.hover(Some(2), 0);
+    runtime.hover(Some(1), 0);
+    runtime.hover(None, 0);
+    assert_eq!(sink.messages(), vec!["in 2", "out 1"]);
+    sink.clear();
+
+    // hiding 1 hides 2 as well
+    runtime.world_mut().write_pointer("/nodes/1/extensions/KHR_node_visibility/visible", Value::Bool(false)).unwrap();
+    assert!(runtime.world().is_visible(0));
+    assert!(!runtime.world().is_visible(2));
+    runtime.hover(Some(2), 0);
+    assert!(sink.messages().is_empty());
+    runtime.world_mut().write_pointer("/nodes/1/extensions/KHR_node_visibility/visible", Value::Bool(true)).unwrap();
+    assert!(runtime.world().is_visible(2));
+    runtime.hover(Some(2), 0);
+    assert_eq!(sink.messages(), vec!["in 2"]);
+}
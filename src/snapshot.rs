@@ -0,0 +1,201 @@
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use serde::{Deserialize, Serialize};
+use crate::{CustomEvent, ExistingValues, Interpolation, NodeId, PendingFlow, Variables};
+
+/// Everything a [`crate::Runtime`] needs to continue where it was, for save
+/// games: the last computed values, variables, the state nodes keep between
+/// activations, deferred flows, interpolations and queued events. The graph,
+/// the world, subscribers and the log sink are not part of it; a snapshot is
+/// restored into a runtime built from the same graph.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub(crate) existing_values: ExistingValues,
+    pub(crate) variables: Variables,
+    pub(crate) node_states: BTreeMap<NodeId, serde_json::Value>,
+    pub(crate) pending_events: VecDeque<CustomEvent>,
+    pub(crate) pending_flows: Vec<PendingFlow>,
+    pub(crate) interpolations: Vec<Interpolation>,
+    pub(crate) hovered: HashMap<i32, usize>,
+    pub(crate) started: bool,
+    pub(crate) time_since_start: f32,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum SnapshotError {
+    #[error("snapshot is not valid JSON: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("snapshot has state for node {0}, which is not in the graph")]
+    UnknownNode(NodeId),
+    #[error("state of node {node_id} does not fit it: {source}")]
+    NodeState { node_id: NodeId, source: serde_json::Error },
+}
+
+impl Snapshot {
+    pub fn from_json(json: &str) -> Result<Self, SnapshotError> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+
+    pub fn time_since_start(&self) -> f32 {
+        self.time_since_start
+    }
+}
+
+#[test]
+fn test_snapshot() {
+    use crate::{CollectingLogSink, EventDeclaration, EventReceiveNode, ExecutionContext, Graph, LogNode, NodeBehavior, NodeBehaviors, NodeDescriptor, NodeKind, OnStartNode, OnTickNode, Runtime, Severity, Value, ValueType, VariableDeclaration, VariableGetNode, VariableInterpolateNode};
+
+    /// Counts its activations, which only a snapshot of its state can carry over.
+    #[derive(Serialize, Deserialize)]
+    struct CounterNode {
+        node_id: NodeId,
+        count: i32,
+    }
+
+    impl NodeBehavior for CounterNode {
+        fn node_id(&self) -> NodeId {
+            self.node_id
+        }
+
+        fn layout(&self) -> NodeDescriptor {
+            NodeDescriptor::new("test/counter", NodeKind::Flow, "Counts its activations.")
+                .input_flow("in")
+                .output_value("count", ValueType::Int)
+                .output_flow("out")
+        }
+
+        fn value(&mut self, _: &mut ExecutionContext) -> Option<()> {
+            Some(())
+        }
+
+        fn activate_input_node(&mut self, ctx: &mut ExecutionContext) -> Option<()> {
+            self.count += 1;
+            ctx.existing_values.set_value(self.node_id, "count", Value::Int(self.count));
+            ctx.fire(self.node_id, "out")
+        }
+
+        fn state(&self) -> Option<serde_json::Value> {
+            Some(self.count.into())
+        }
+
+        fn restore_state(&mut self, state: serde_json::Value) -> Result<(), serde_json::Error> {
+            self.count = serde_json::from_value(state)?;
+            Ok(())
+        }
+    }
+
+    let ping = EventDeclaration {
+        id: "ping".to_string(),
+        values: vec![],
+    };
+
+    // onStart interpolates x to 1 over a second, every tick logs the count of
+    // ticks and x, and the ping event is logged.
+    let build = || {
+        let mut graph = Graph::new();
+        let mut node_behaviors = NodeBehaviors(HashMap::new());
+
+        let start_id = 0;
+        let interpolate_id = 1;
+        let done_id = 2;
+        let tick_id = 3;
+        let counter_id = 4;
+        let tick_log_id = 5;
+        let x_id = 6;
+        let receive_id = 7;
+        let ping_log_id = 8;
+        let constant_ids = [9, 10, 11, 12];
+
+        let start = OnStartNode::new(start_id);
+        let interpolate = VariableInterpolateNode::new(interpolate_id, 0);
+        let done = LogNode::new(done_id, "done", Severity::Info);
+        let tick = OnTickNode::new(tick_id);
+        let counter = CounterNode {
+            node_id: counter_id,
+            count: 0,
+        };
+        let tick_log = LogNode::new(tick_log_id, "tick {count}, x is {value}", Severity::Info);
+        let x = VariableGetNode::new(x_id, 0);
+        let receive = EventReceiveNode::new(receive_id, 0, &ping);
+        let ping_log = LogNode::new(ping_log_id, "ping", Severity::Info);
+
+        graph
+            .add_node(start.node())
+            .add_node(interpolate.node())
+            .add_node(done.node())
+            .add_node(tick.node())
+            .add_node(counter.node())
+            .add_node(tick_log.node())
+            .add_node(x.node())
+            .add_node(receive.node())
+            .add_node(ping_log.node())
+            .link_flow(start_id, "out", interpolate_id, "in")
+            .link_flow(interpolate_id, "done", done_id, "in")
+            .link_flow(tick_id, "out", counter_id, "in")
+            .link_flow(counter_id, "out", tick_log_id, "in")
+            .link_flow(receive_id, "out", ping_log_id, "in")
+            .link_value(counter_id, "count", tick_log_id, "count")
+            .link_value(x_id, "value", tick_log_id, "value");
+
+        node_behaviors.add(Box::new(start));
+        node_behaviors.add(Box::new(interpolate));
+        node_behaviors.add(Box::new(done));
+        node_behaviors.add(Box::new(tick));
+        node_behaviors.add(Box::new(counter));
+        node_behaviors.add(Box::new(tick_log));
+        node_behaviors.add(Box::new(x));
+        node_behaviors.add(Box::new(receive));
+        node_behaviors.add(Box::new(ping_log));
+        for (i, (node_id, socket)) in constant_ids.into_iter().zip(["value", "duration", "p1", "p2"]).enumerate() {
+            let constant = VariableGetNode::new(node_id, i + 1);
+            graph
+                .add_node(constant.node())
+                .link_value(node_id, "value", interpolate_id, socket);
+            node_behaviors.add(Box::new(constant));
+        }
+
+        let sink = CollectingLogSink::default();
+        let mut runtime = Runtime::new(graph.resolve().unwrap(), node_behaviors);
+        runtime.set_log_sink(sink.clone());
+        let declarations = [
+            ("x", Value::Float(0.0)),
+            ("target", Value::Float(1.0)),
+            ("duration", Value::Float(1.0)),
+            ("p1", Value::Float2([0.0, 0.0])),
+            ("p2", Value::Float2([1.0, 1.0])),
+        ];
+        for (name, value) in declarations {
+            runtime.add_variable(VariableDeclaration {
+                name: name.to_string(),
+                value_type: value.value_type(),
+                value,
+            }).unwrap();
+        }
+        runtime.add_event(ping.clone());
+        (runtime, sink)
+    };
+
+    let (mut original, original_sink) = build();
+    original.tick(0.25);
+    original.send_event(0, HashMap::new()).unwrap();
+    let snapshot = original.snapshot().to_json();
+    original_sink.clear();
+    original.tick(0.25);
+    original.tick(0.5);
+    assert_eq!(original_sink.messages(), vec!["ping", "tick 2, x is 0.5", "done", "tick 3, x is 1"]);
+
+    let (mut restored, restored_sink) = build();
+    restored.restore(Snapshot::from_json(&snapshot).unwrap()).unwrap();
+    assert_eq!(restored.time_since_start(), 0.25);
+    restored.start();
+    restored.tick(0.25);
+    restored.tick(0.5);
+    assert_eq!(restored_sink.messages(), original_sink.messages());
+
+    let mut snapshot = Snapshot::from_json(&snapshot).unwrap();
+    snapshot.node_states.insert(99, serde_json::Value::Null);
+    assert!(matches!(build().0.restore(snapshot), Err(SnapshotError::UnknownNode(99))));
+}
@@ -0,0 +1,176 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+use serde::{Deserialize, Serialize};
+use crate::{NodeId, Value};
+
+/// Something the graph did, as seen by a [`TraceSink`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum TraceKind {
+    /// The input flow of `node_id` was activated.
+    NodeActivated {
+        node_id: NodeId,
+        op: String,
+    },
+    /// `node_id` computed the value of its output value socket `socket`.
+    ValueComputed {
+        node_id: NodeId,
+        socket: String,
+        value: Value,
+    },
+    /// The output flow socket `socket` of `node_id` fired.
+    FlowFired {
+        node_id: NodeId,
+        socket: String,
+    },
+    PointerWritten {
+        pointer: String,
+        value: Value,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TraceEvent {
+    /// The wall-clock time since the runtime was created.
+    pub timestamp: Duration,
+    pub kind: TraceKind,
+}
+
+/// Where trace events end up, see [`crate::Runtime::add_trace_sink`].
+pub trait TraceSink {
+    fn trace(&mut self, event: &TraceEvent);
+}
+
+/// Hands trace events to every sink. Without sinks, events are not even built.
+pub struct Tracer {
+    sinks: Vec<Box<dyn TraceSink>>,
+    epoch: Instant,
+}
+
+impl Default for Tracer {
+    fn default() -> Self {
+        Self {
+            sinks: vec![],
+            epoch: Instant::now(),
+        }
+    }
+}
+
+impl Tracer {
+    pub fn add_sink(&mut self, sink: impl TraceSink + 'static) {
+        self.sinks.push(Box::new(sink));
+    }
+
+    /// Sends the event `kind` builds to every sink, if there are any.
+    pub fn trace(&mut self, kind: impl FnOnce() -> TraceKind) {
+        if self.sinks.is_empty() {
+            return;
+        }
+        let event = TraceEvent {
+            timestamp: self.epoch.elapsed(),
+            kind: kind(),
+        };
+        for sink in &mut self.sinks {
+            sink.trace(&event);
+        }
+    }
+}
+
+/// Keeps every event to write them in the Chrome trace event format, which
+/// `chrome://tracing` and Perfetto open. Clones share the same events, like
+/// [`crate::CollectingLogSink`].
+#[derive(Debug, Clone, Default)]
+pub struct ChromeTraceSink(Rc<RefCell<Vec<TraceEvent>>>);
+
+impl ChromeTraceSink {
+    pub fn events(&self) -> Vec<TraceEvent> {
+        self.0.borrow().clone()
+    }
+
+    /// The events as instant events of a single thread, with the node, socket
+    /// and value as arguments.
+    pub fn to_json(&self) -> String {
+        let events = self.0.borrow().iter().map(|event| {
+            let (name, category, args) = match &event.kind {
+                TraceKind::NodeActivated { node_id, op } => (format!("activate {op}"), "activation", serde_json::json!({ "node": node_id })),
+                TraceKind::ValueComputed { node_id, socket, value } => (format!("value {socket}"), "value", serde_json::json!({ "node": node_id, "socket": socket, "value": value })),
+                TraceKind::FlowFired { node_id, socket } => (format!("flow {socket}"), "flow", serde_json::json!({ "node": node_id, "socket": socket })),
+                TraceKind::PointerWritten { pointer, value } => (format!("write {pointer}"), "pointer", serde_json::json!({ "pointer": pointer, "value": value })),
+            };
+            serde_json::json!({
+                "name": name,
+                "cat": category,
+                "ph": "i",
+                "s": "t",
+                "ts": event.timestamp.as_micros() as u64,
+                "pid": 0,
+                "tid": 0,
+                "args": args,
+            })
+        }).collect::<Vec<_>>();
+        serde_json::json!({ "traceEvents": events }).to_string()
+    }
+}
+
+impl TraceSink for ChromeTraceSink {
+    fn trace(&mut self, event: &TraceEvent) {
+        self.0.borrow_mut().push(event.clone());
+    }
+}
+
+#[test]
+fn test_trace() {
+    use std::collections::HashMap;
+    use crate::{CollectingLogSink, GltfWorld, Graph, LogNode, MathPi, NodeBehavior, NodeBehaviors, OnStartNode, PointerSetNode, PointerTemplate, Runtime, Severity};
+
+    let mut graph = Graph::new();
+    let mut node_behaviors = NodeBehaviors(HashMap::new());
+
+    let start_id = 0;
+    let set_id = 1;
+    let pi_id = 2;
+    let log_id = 3;
+
+    let start = OnStartNode::new(start_id);
+    let set = PointerSetNode::new(set_id, PointerTemplate::parse("/materials/0/alphaCutoff").unwrap()).unwrap();
+    let pi = MathPi::new(pi_id);
+    let log = LogNode::new(log_id, "set", Severity::Info);
+
+    graph
+        .add_node(start.node())
+        .add_node(set.node())
+        .add_node(pi.node())
+        .add_node(log.node())
+        .link_flow(start_id, "out", set_id, "in")
+        .link_flow(set_id, "out", log_id, "in")
+        .link_value(pi_id, "value", set_id, "value");
+
+    node_behaviors.add(Box::new(start));
+    node_behaviors.add(Box::new(set));
+    node_behaviors.add(Box::new(pi));
+    node_behaviors.add(Box::new(log));
+
+    let world = GltfWorld::from_json(r#"{ "materials": [{}] }"#).unwrap();
+    let sink = ChromeTraceSink::default();
+    let mut runtime = Runtime::with_world(graph.resolve().unwrap(), node_behaviors, world);
+    runtime.set_log_sink(CollectingLogSink::default());
+    runtime.add_trace_sink(sink.clone());
+    runtime.start();
+
+    let pi = Value::Float(std::f32::consts::PI);
+    let kinds = sink.events().into_iter().map(|a| a.kind).collect::<Vec<_>>();
+    assert_eq!(kinds, vec![
+        TraceKind::FlowFired { node_id: start_id, socket: "out".to_string() },
+        TraceKind::NodeActivated { node_id: set_id, op: "pointer/set".to_string() },
+        TraceKind::ValueComputed { node_id: pi_id, socket: "value".to_string(), value: pi.clone() },
+        TraceKind::PointerWritten { pointer: "/materials/0/alphaCutoff".to_string(), value: pi },
+        TraceKind::FlowFired { node_id: set_id, socket: "out".to_string() },
+        TraceKind::NodeActivated { node_id: log_id, op: "debug/log".to_string() },
+        TraceKind::FlowFired { node_id: log_id, socket: "out".to_string() },
+    ]);
+
+    let json: serde_json::Value = serde_json::from_str(&sink.to_json()).unwrap();
+    let names = json["traceEvents"].as_array().unwrap().iter().map(|a| a["name"].as_str().unwrap().to_string()).collect::<Vec<_>>();
+    assert_eq!(names[1], "activate pointer/set");
+    assert_eq!(names[3], "write /materials/0/alphaCutoff");
+}
@@ -0,0 +1,356 @@
+use serde::{Deserialize, Serialize};
+use crate::{node, ConfigurationError, Declarations, ExecutionContext, NodeId, Severity, Value, ValueType};
+
+/// A graph variable, with the type it holds and the value it starts with.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VariableDeclaration {
+    pub name: String,
+    pub value_type: ValueType,
+    pub value: Value,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum VariableError {
+    #[error("no variable is declared at index {0}")]
+    UnknownVariable(usize),
+    #[error("no variable is named `{0}`")]
+    UnknownName(String),
+    #[error("variable `{name}` holds {expected:?}, not {found:?}")]
+    TypeMismatch { name: String, expected: ValueType, found: ValueType },
+}
+
+/// The current values of the variables a graph declares, stored by the runtime
+/// next to its [`ExistingValues`].
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Variables {
+    declarations: Vec<VariableDeclaration>,
+    values: Vec<Value>,
+}
+
+impl Variables {
+    /// Declares a variable holding its initial value and returns its index.
+    pub fn add(&mut self, declaration: VariableDeclaration) -> Result<usize, VariableError> {
+        if declaration.value.value_type() != declaration.value_type {
+            return Err(VariableError::TypeMismatch {
+                name: declaration.name,
+                expected: declaration.value_type,
+                found: declaration.value.value_type(),
+            });
+        }
+        self.values.push(declaration.value.clone());
+        self.declarations.push(declaration);
+        Ok(self.values.len() - 1)
+    }
+
+    pub fn declarations(&self) -> &[VariableDeclaration] {
+        &self.declarations
+    }
+
+    pub fn index(&self, name: &str) -> Option<usize> {
+        self.declarations.iter().position(|a| a.name == name)
+    }
+
+    pub fn get(&self, index: usize) -> Option<&Value> {
+        self.values.get(index)
+    }
+
+    /// Checks that the variable at `index` is declared and accepts `value`.
+    pub fn check(&self, index: usize, value: &Value) -> Result<(), VariableError> {
+        let declaration = self.declarations.get(index).ok_or(VariableError::UnknownVariable(index))?;
+        if value.value_type() != declaration.value_type {
+            return Err(VariableError::TypeMismatch {
+                name: declaration.name.clone(),
+                expected: declaration.value_type,
+                found: value.value_type(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Sets a variable, which only ever accepts values of its declared type.
+    pub fn set(&mut self, index: usize, value: Value) -> Result<(), VariableError> {
+        self.check(index, &value)?;
+        self.values[index] = value;
+        Ok(())
+    }
+}
+
+/// The variable at `index`, from the configuration entry `name` of a node,
+/// which has to be one of `declarations`.
+pub(crate) fn declared_variable(name: &str, index: usize, declarations: &Declarations) -> Result<usize, ConfigurationError> {
+    if index >= declarations.variables.len() {
+        return Err(ConfigurationError::invalid(name, format!("no variable is declared at index {index}")));
+    }
+    Ok(index)
+}
+
+/// `variable/get`, exposes the current value of `variable`.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct VariableGetNode {
+    node_id: NodeId,
+    variable: usize,
+}
+
+impl VariableGetNode {
+    pub fn new(node_id: NodeId, variable: usize) -> Self {
+        Self {
+            node_id,
+            variable,
+        }
+    }
+}
+
+node! {
+    VariableGetNode {
+        op: "variable/get",
+        kind: Pure,
+        description: "Exposes the current value of a variable.",
+        output_values: { set_value = "value": Value },
+        configuration: |node| { "variable": Int(node.variable as i32) },
+        new_node: |node_id, configuration, declarations| Self::new(node_id, declared_variable("variable", configuration.index("variable")?.unwrap_or(0), declarations)?),
+    }
+    impl NodeBehavior {
+        fn value(&mut self, ctx: &mut ExecutionContext) -> Option<()> {
+            let Some(value) = ctx.variables.get(self.variable).cloned() else {
+                ctx.log.log(Severity::Error, &format!("variable/get node {} cannot read: {}", self.node_id, VariableError::UnknownVariable(self.variable)));
+                return Some(());
+            };
+            self.set_value(ctx, value)
+        }
+
+        fn activate_input_node(&mut self, _: &mut ExecutionContext) -> Option<()> {
+            panic!("cannot activate this node");
+        }
+    }
+}
+
+/// `variable/set`, sets `variable` to its `value` input.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct VariableSetNode {
+    node_id: NodeId,
+    variable: usize,
+}
+
+impl VariableSetNode {
+    pub fn new(node_id: NodeId, variable: usize) -> Self {
+        Self {
+            node_id,
+            variable,
+        }
+    }
+}
+
+node! {
+    VariableSetNode {
+        op: "variable/set",
+        kind: Flow,
+        description: "Sets a variable to `value`.",
+        input_values: { new_value = "value": Value },
+        input_flows: ["in"],
+        output_flows: ["out"],
+        configuration: |node| { "variable": Int(node.variable as i32) },
+        new_node: |node_id, configuration, declarations| Self::new(node_id, declared_variable("variable", configuration.index("variable")?.unwrap_or(0), declarations)?),
+    }
+    impl NodeBehavior {
+        fn value(&mut self, _: &mut ExecutionContext) -> Option<()> {
+            panic!("cannot request value from this node");
+        }
+
+        fn activate_input_node(&mut self, ctx: &mut ExecutionContext) -> Option<()> {
+            let value = self.new_value(ctx)?;
+            if let Err(error) = ctx.set_variable(self.variable, value) {
+                ctx.log.log(Severity::Error, &format!("variable/set node {} cannot set: {error}", self.node_id));
+                return Some(());
+            }
+            ctx.fire(self.node_id, "out")
+        }
+    }
+}
+
+/// `variable/setMultiple`, sets each of `variables` to the input value socket
+/// named after its index.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct VariableSetMultipleNode {
+    node_id: NodeId,
+    variables: Vec<usize>,
+}
+
+impl VariableSetMultipleNode {
+    pub fn new(node_id: NodeId, variables: Vec<usize>) -> Self {
+        Self {
+            node_id,
+            variables,
+        }
+    }
+
+    fn socket_names(&self) -> Vec<String> {
+        self.variables.iter().map(|a| a.to_string()).collect()
+    }
+}
+
+node! {
+    VariableSetMultipleNode {
+        op: "variable/setMultiple",
+        kind: Flow,
+        description: "Sets several variables, each to the input named after its index.",
+        input_flows: ["in"],
+        output_flows: ["out"],
+        configuration: |node| { "variables": IntArray(node.variables.iter().map(|a| *a as i32).collect()) },
+        new_node: |node_id, configuration, declarations| {
+            let variables = configuration.indices("variables")?.unwrap_or_default().into_iter()
+                .map(|a| declared_variable("variables", a, declarations))
+                .collect::<Result<_, _>>()?;
+            Self::new(node_id, variables)
+        },
+        layout: |node, descriptor| node.socket_names().iter().fold(descriptor, |a, b| a.input_any(b)),
+    }
+    impl NodeBehavior {
+        fn value(&mut self, _: &mut ExecutionContext) -> Option<()> {
+            panic!("cannot request value from this node");
+        }
+
+        fn activate_input_node(&mut self, ctx: &mut ExecutionContext) -> Option<()> {
+            // Read every input before writing anything, so a value computed from
+            // one of the variables sees its old value.
+            let mut values = vec![];
+            for variable in &self.variables {
+                values.push((*variable, ctx.input_value(self.node_id, &variable.to_string())?));
+            }
+            // Set all of them or none.
+            if let Some(error) = values.iter().find_map(|(variable, value)| ctx.variables.check(*variable, value).err()) {
+                ctx.log.log(Severity::Error, &format!("variable/setMultiple node {} cannot set: {error}", self.node_id));
+                return Some(());
+            }
+            for (variable, value) in values {
+                ctx.set_variable(variable, value).ok()?;
+            }
+            ctx.fire(self.node_id, "out")
+        }
+    }
+}
+
+#[test]
+fn test_variables() {
+    use std::collections::HashMap;
+    use crate::{Graph, LogNode, MathPi, NodeBehavior, NodeBehaviors, OnStartNode, PrintNode, Runtime, Severity};
+
+    let mut graph = Graph::new();
+    let mut node_behaviors = NodeBehaviors(HashMap::new());
+
+    let start_id = 0;
+    let set_id = 1;
+    let set_multiple_id = 2;
+    let print_id = 3;
+    let pi_id = 4;
+    let get_id = 5;
+    let log_id = 6;
+    let get_previous_id = 7;
+
+    let start = OnStartNode::new(start_id);
+    let set = VariableSetNode::new(set_id, 0);
+    let set_multiple = VariableSetMultipleNode::new(set_multiple_id, vec![1, 0]);
+    let print = PrintNode::new(print_id);
+    let pi = MathPi::new(pi_id);
+    let get = VariableGetNode::new(get_id, 0);
+    let log = LogNode::new(log_id, "{previous}", Severity::Info);
+    let get_previous = VariableGetNode::new(get_previous_id, 1);
+
+    graph
+        .add_node(start.node())
+        .add_node(set.node())
+        .add_node(set_multiple.node())
+        .add_node(print.node())
+        .add_node(pi.node())
+        .add_node(get.node())
+        .add_node(log.node())
+        .add_node(get_previous.node())
+        .link_flow(start_id, "out", set_id, "in")
+        .link_flow(set_id, "out", set_multiple_id, "in")
+        .link_flow(set_multiple_id, "out", log_id, "in")
+        .link_flow(log_id, "out", print_id, "print_input")
+        .link_value(pi_id, "value", set_id, "value")
+        .link_value(get_id, "value", set_multiple_id, "1")
+        .link_value(pi_id, "value", set_multiple_id, "0")
+        .link_value(get_id, "value", print_id, "print_value")
+        .link_value(get_previous_id, "value", log_id, "previous");
+
+    node_behaviors.add(Box::new(start));
+    node_behaviors.add(Box::new(set));
+    node_behaviors.add(Box::new(set_multiple));
+    node_behaviors.add(Box::new(print));
+    node_behaviors.add(Box::new(pi));
+    node_behaviors.add(Box::new(get));
+    node_behaviors.add(Box::new(log));
+    node_behaviors.add(Box::new(get_previous));
+
+    let mut runtime = Runtime::new(graph.resolve().unwrap(), node_behaviors);
+    runtime.add_variable(VariableDeclaration {
+        name: "angle".to_string(),
+        value_type: ValueType::Float,
+        value: Value::Float(1.0),
+    }).unwrap();
+    runtime.add_variable(VariableDeclaration {
+        name: "previous".to_string(),
+        value_type: ValueType::Float,
+        value: Value::Float(0.0),
+    }).unwrap();
+    assert_eq!(runtime.variable_by_name("angle"), Some(&Value::Float(1.0)));
+
+    runtime.start();
+    let pi = Value::Float(std::f32::consts::PI);
+    assert_eq!(runtime.variable(0), Some(&pi));
+    assert_eq!(runtime.variable_by_name("previous"), Some(&pi));
+    assert_eq!(runtime.existing_values().get_value(get_id, "value"), Some(&pi));
+
+    // Values are computed again on every request, so `get` is only updated
+    // once something reads it.
+    runtime.set_variable_by_name("angle", Value::Float(2.0)).unwrap();
+    assert_eq!(runtime.variable_by_name("angle"), Some(&Value::Float(2.0)));
+    assert_eq!(runtime.existing_values().get_value(get_id, "value"), Some(&pi));
+    assert!(runtime.set_variable(0, Value::Int(2)).is_err());
+    assert!(runtime.set_variable(2, Value::Float(2.0)).is_err());
+}
+
+#[test]
+fn test_variable_errors() {
+    use crate::{CollectingLogSink, Configuration, Graph, LoadError, NodeRegistry, Runtime};
+
+    let registry = NodeRegistry::builtin();
+    let mut graph = Graph::new();
+    graph.declarations.variables.push(VariableDeclaration {
+        name: "x".to_string(),
+        value_type: ValueType::Float,
+        value: Value::Float(0.0),
+    });
+    let build = |op: &str, node_id: NodeId, configuration: Configuration| registry.build(op, node_id, &configuration, &graph.declarations);
+
+    // Variables are looked up when the graph is loaded.
+    assert!(matches!(
+        build("variable/get", 0, Configuration::new().with("variable", 1)),
+        Err(LoadError::Configuration { source: ConfigurationError::Invalid { .. }, .. })
+    ));
+    assert!(matches!(
+        build("variable/setMultiple", 0, Configuration::new().with("variables", vec![0, 3])),
+        Err(LoadError::Configuration { source: ConfigurationError::Invalid { .. }, .. })
+    ));
+
+    // A value of the wrong type is reported, and nothing after the node runs.
+    let start = build("event/onStart", 0, Configuration::new()).unwrap();
+    let set = build("variable/set", 1, Configuration::new().with("variable", 0)).unwrap();
+    let set_multiple = build("variable/setMultiple", 2, Configuration::new().with("variables", vec![0])).unwrap();
+    let log = build("debug/log", 3, Configuration::new().with("message", "set")).unwrap();
+    graph
+        .add_node(start)
+        .add_node(set.with_value("value", Value::Float(1.0)))
+        .add_node(set_multiple.with_value("0", Value::Int(2)))
+        .add_node(log)
+        .link_flow(0, "out", 1, "in")
+        .link_flow(1, "out", 2, "in")
+        .link_flow(2, "out", 3, "in");
+    let sink = CollectingLogSink::default();
+    let mut runtime = Runtime::load(&registry, &graph).unwrap();
+    runtime.set_log_sink(sink.clone());
+    runtime.start();
+    assert_eq!(runtime.variable(0), Some(&Value::Float(1.0)));
+    assert_eq!(sink.messages(), vec!["variable/setMultiple node 2 cannot set: variable `x` holds Float, not Int"]);
+}
@@ -0,0 +1,181 @@
+use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use crate::Value;
+
+/// Something that happened in the scene, picked up by the runtime on the next tick.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum WorldEvent {
+    /// The animation with this index finished playing or reached its stop time.
+    AnimationCompleted(i32),
+    /// A custom event the host sends to the graph's `event/receive` nodes.
+    Custom {
+        event: usize,
+        values: HashMap<String, Value>,
+    },
+    /// A controller selected a node, see [`crate::Runtime::select`].
+    Select {
+        node: usize,
+        controller: i32,
+        point: [f32; 3],
+        ray_origin: [f32; 3],
+    },
+    /// A controller's hovered node changed, see [`crate::Runtime::hover`].
+    Hover {
+        node: Option<usize>,
+        controller: i32,
+    },
+}
+
+/// The scene a graph runs against, which is how an engine plugs its own scene
+/// representation into the runtime. `pointer/get` and `pointer/set` read and
+/// write its properties through resolved JSON pointers such as
+/// `/nodes/3/translation`, the `animation/*` nodes control its animations, and
+/// the runtime takes injected events and the passing of time from it. Nodes
+/// reach it through [`crate::ExecutionContext::world`].
+pub trait World {
+    /// The current value of the property at `pointer`, or `None` if the
+    /// pointer does not resolve.
+    fn read_pointer(&self, pointer: &str) -> Option<Value>;
+
+    /// Writes the property at `pointer`, returning `None` if the pointer does
+    /// not resolve or the value cannot be stored there.
+    fn write_pointer(&mut self, pointer: &str, value: Value) -> Option<()>;
+
+    /// Plays the animation with index `animation` from `start_time` to
+    /// `end_time` at `speed`, returning `None` if there is no such animation.
+    /// The host reports the end of playback with
+    /// [`crate::Runtime::complete_animation`] or [`WorldEvent::AnimationCompleted`].
+    fn start_animation(&mut self, _animation: i32, _start_time: f32, _end_time: f32, _speed: f32) -> Option<()> {
+        None
+    }
+
+    /// Stops the animation with index `animation` right away.
+    fn stop_animation(&mut self, _animation: i32) -> Option<()> {
+        None
+    }
+
+    /// Stops the animation with index `animation` once it reaches `stop_time`.
+    /// The host reports that like the end of playback.
+    fn stop_animation_at(&mut self, _animation: i32, _stop_time: f32) -> Option<()> {
+        None
+    }
+
+    /// The parent of the glTF node with index `node`, if it has one.
+    fn parent(&self, _node: usize) -> Option<usize> {
+        None
+    }
+
+    /// Whether the glTF node with index `node` itself can be selected,
+    /// following `KHR_node_selectability`. Ancestors are checked by the runtime.
+    fn selectable(&self, _node: usize) -> bool {
+        true
+    }
+
+    /// Whether the glTF node with index `node` itself can be hovered,
+    /// following `KHR_node_hoverability`. Ancestors are checked by the runtime.
+    fn hoverable(&self, _node: usize) -> bool {
+        true
+    }
+
+    /// Whether the glTF node with index `node` itself is visible, following
+    /// `KHR_node_visibility`. Hidden nodes and their descendants can be neither
+    /// selected nor hovered.
+    fn visible(&self, _node: usize) -> bool {
+        true
+    }
+
+    /// Lets the scene move `dt` seconds on, e.g. to play its animations. Called
+    /// by the runtime at the start of every tick.
+    fn advance(&mut self, _dt: f32) {}
+
+    /// The events raised since the runtime last asked, in order. This is an
+    /// alternative to calling [`crate::Runtime::send_event`] and
+    /// [`crate::Runtime::complete_animation`] directly.
+    fn take_events(&mut self) -> Vec<WorldEvent> {
+        vec![]
+    }
+
+    /// The seconds the scene advanced since the runtime last asked, used by
+    /// [`crate::Runtime::update`].
+    fn delta_time(&mut self) -> f32 {
+        0.0
+    }
+}
+
+/// A world without any properties, for graphs that never touch the scene.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EmptyWorld;
+
+impl World for EmptyWorld {
+    fn read_pointer(&self, _: &str) -> Option<Value> {
+        None
+    }
+
+    fn write_pointer(&mut self, _: &str, _: Value) -> Option<()> {
+        None
+    }
+}
+
+#[test]
+fn test_world_events_and_time() {
+    use crate::{CollectingLogSink, EventDeclaration, EventReceiveNode, Graph, LogNode, NodeBehavior, NodeBehaviors, Runtime, Severity};
+
+    #[derive(Default)]
+    struct TestWorld {
+        events: Vec<WorldEvent>,
+    }
+
+    impl World for TestWorld {
+        fn read_pointer(&self, _: &str) -> Option<Value> {
+            None
+        }
+
+        fn write_pointer(&mut self, _: &str, _: Value) -> Option<()> {
+            None
+        }
+
+        fn take_events(&mut self) -> Vec<WorldEvent> {
+            std::mem::take(&mut self.events)
+        }
+
+        fn delta_time(&mut self) -> f32 {
+            0.5
+        }
+    }
+
+    let mut graph = Graph::new();
+    let mut node_behaviors = NodeBehaviors(HashMap::new());
+
+    let clicked = EventDeclaration {
+        id: "clicked".to_string(),
+        values: vec![],
+    };
+    let receive_id = 0;
+    let log_id = 1;
+    let receive = EventReceiveNode::new(receive_id, 0, &clicked);
+    let log = LogNode::new(log_id, "clicked", Severity::Info);
+
+    graph
+        .add_node(receive.node())
+        .add_node(log.node())
+        .link_flow(receive_id, "out", log_id, "in");
+    node_behaviors.add(Box::new(receive));
+    node_behaviors.add(Box::new(log));
+
+    let sink = CollectingLogSink::default();
+    let mut runtime = Runtime::with_world(graph.resolve().unwrap(), node_behaviors, TestWorld::default());
+    runtime.set_log_sink(sink.clone());
+    let event = runtime.add_event(clicked);
+
+    runtime.world_mut().events.push(WorldEvent::Custom {
+        event,
+        values: HashMap::new(),
+    });
+    runtime.world_mut().events.push(WorldEvent::Custom {
+        event: 7,
+        values: HashMap::new(),
+    });
+    runtime.update();
+    assert_eq!(runtime.time_since_start(), 0.5);
+    assert_eq!(sink.messages(), vec!["no event is declared at index 7", "clicked"]);
+}